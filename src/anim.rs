@@ -0,0 +1,91 @@
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+use anathema::Size;
+use anyhow::{anyhow, Result};
+
+use crate::display::anim_file::{AnimFile, CURRENT_VERSION};
+use crate::display::{preview_anim, ANIMATION_DIR};
+
+const USAGE: &str = "usage:
+    witchbox2 anim preview <file> [--size WIDTHxHEIGHT] [--text TEXT] [--name NAME]
+    witchbox2 anim lint [files...]";
+
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    match args.next().as_deref() {
+        Some("preview") => preview(args),
+        Some("lint") => lint(args),
+        _ => Err(anyhow!(USAGE)),
+    }
+}
+
+fn parse_size(s: &str) -> Result<Size> {
+    let (width, height) = s.split_once('x').ok_or_else(|| anyhow!("Size should look like 80x24, not {}", s))?;
+    Ok(Size::new(width.parse()?, height.parse()?))
+}
+
+// -----------------------------------------------------------------------------
+//     - Preview -
+// -----------------------------------------------------------------------------
+fn preview(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut path = None;
+    let mut size = None;
+    let mut text = None;
+    let mut name = "Preview".to_string();
+
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--size" => size = Some(parse_size(&args.next().ok_or_else(|| anyhow!(USAGE))?)?),
+            "--text" => text = args.next(),
+            "--name" => name = args.next().ok_or_else(|| anyhow!(USAGE))?,
+            _ => path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let path = path.ok_or_else(|| anyhow!(USAGE))?;
+    preview_anim(&path, size, text.as_deref(), &name)
+}
+
+// -----------------------------------------------------------------------------
+//     - Lint -
+// -----------------------------------------------------------------------------
+fn lint_file(path: &Path) -> Vec<String> {
+    match AnimFile::load(path) {
+        Ok(anim) => {
+            // Older versions still work, so this is only a note
+            if anim.version < CURRENT_VERSION {
+                println!("{}: note: uses version {}, the newest is {}", path.display(), anim.version, CURRENT_VERSION);
+            }
+            anim.lint()
+        }
+        Err(e) => vec![e.to_string()],
+    }
+}
+
+fn lint(args: impl Iterator<Item = String>) -> Result<()> {
+    let mut paths = args.map(PathBuf::from).collect::<Vec<_>>();
+
+    // Lint every animation if no files are given
+    if paths.is_empty() {
+        paths = read_dir(ANIMATION_DIR)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().map(|ext| ext == "txt").unwrap_or(false))
+            .collect();
+        paths.sort();
+    }
+
+    let mut problem_count = 0;
+    for path in &paths {
+        let problems = lint_file(path);
+        match problems.is_empty() {
+            true => println!("{}: ok", path.display()),
+            false => problems.iter().for_each(|p| println!("{}: {}", path.display(), p)),
+        }
+        problem_count += problems.len();
+    }
+
+    match problem_count {
+        0 => Ok(()),
+        n => Err(anyhow!("{} problem(s) in {} file(s)", n, paths.len())),
+    }
+}
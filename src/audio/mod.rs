@@ -1,7 +1,9 @@
 use rand::prelude::*;
 
 mod sound_player;
+mod tts;
 pub use sound_player::SoundPlayer;
+pub use tts::Speaker;
 
 pub fn default_sound() -> String {
     "/home/togglebit/projects/stream/misc/default.mp3".into()
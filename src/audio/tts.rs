@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+use std::io::{Cursor, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use rodio::{Decoder, OutputStreamHandle, Sink};
+
+use crate::display::models::Speech;
+
+// Turn text into a wav with the configured command.
+// The text goes in on stdin and the wav comes out on stdout.
+fn synthesize(command: &[String], speech: &Speech) -> Result<Vec<u8>> {
+    let (program, args) = command.split_first().ok_or_else(|| anyhow!("No TTS command configured"))?;
+
+    let mut child = Command::new(program)
+        .args(args.iter().map(|arg| arg.replace("{voice}", &speech.voice)))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run {}: {}", program, e))?;
+
+    // Dropping stdin closes it, so the engine knows the text is done
+    child.stdin.take().expect("stdin is piped").write_all(speech.text.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{} exited with {}", program, output.status));
+    }
+    Ok(output.stdout)
+}
+
+// -----------------------------------------------------------------------------
+//     - Speaker -
+//     Synthesizing happens on its own thread so the display never waits
+//     on the engine. Finished speech plays one at a time.
+// -----------------------------------------------------------------------------
+pub struct Speaker {
+    requests: Sender<Speech>,
+    ready: Receiver<Vec<u8>>,
+    queue: VecDeque<Vec<u8>>,
+    queue_max: usize,
+    sink: Option<Sink>,
+    output_handle: OutputStreamHandle,
+}
+
+impl Speaker {
+    pub fn new(command: Vec<String>, queue_max: usize, output_handle: OutputStreamHandle) -> Self {
+        let (requests, request_rx) = channel::<Speech>();
+        let (ready_tx, ready) = channel();
+
+        thread::spawn(move || {
+            for speech in request_rx {
+                match synthesize(&command, &speech) {
+                    Ok(wav) => {
+                        if ready_tx.send(wav).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => log::error!("TTS failed: {}", e),
+                }
+            }
+        });
+
+        Self { requests, ready, queue: VecDeque::new(), queue_max, sink: None, output_handle }
+    }
+
+    pub fn say(&mut self, speech: Speech) {
+        if let Err(e) = self.requests.send(speech) {
+            log::error!("TTS is gone: {}", e);
+        }
+    }
+
+    /// Stop whatever is being said right now
+    pub fn skip(&mut self) {
+        // Dropping the sink stops it
+        if self.sink.take().is_some() {
+            log::info!("Skipped TTS");
+        }
+    }
+
+    pub fn update(&mut self) {
+        while let Ok(wav) = self.ready.try_recv() {
+            match self.queue.len() < self.queue_max {
+                true => self.queue.push_back(wav),
+                false => log::warn!("TTS queue is full, dropping speech"),
+            }
+        }
+
+        if !self.sink.as_ref().map(Sink::empty).unwrap_or(true) {
+            return;
+        }
+        self.sink = None;
+
+        let wav = match self.queue.pop_front() {
+            Some(wav) => wav,
+            None => return,
+        };
+
+        match (Decoder::new(Cursor::new(wav)), Sink::try_new(&self.output_handle)) {
+            (Ok(source), Ok(sink)) => {
+                sink.append(source);
+                self.sink = Some(sink);
+            }
+            (Err(e), _) => log::error!("Invalid TTS output: {}", e),
+            (_, Err(e)) => log::error!("No sink for TTS: {:?}", e),
+        }
+    }
+}
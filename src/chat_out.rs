@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::config::ChatOutConfig;
+use crate::router::Publisher;
+
+// Twitch drops anything longer than this
+const MAX_LEN: usize = 500;
+
+/// Say things in Twitch chat.
+/// Messages go out one at a time, as fast as the rate limit allows.
+#[derive(Debug, Clone)]
+pub struct ChatSender(UnboundedSender<String>);
+
+impl ChatSender {
+    pub fn say(&self, text: impl Into<String>) {
+        if let Err(e) = self.0.send(text.into()) {
+            log::error!("Failed to queue chat message: {}", e);
+        }
+    }
+}
+
+pub type ChatReceiver = UnboundedReceiver<String>;
+
+pub fn channel() -> (ChatSender, ChatReceiver) {
+    let (tx, rx) = unbounded_channel();
+    (ChatSender(tx), rx)
+}
+
+// -----------------------------------------------------------------------------
+//     - Rate limit -
+//     At most `max` messages in any `window`.
+// -----------------------------------------------------------------------------
+struct RateLimit {
+    max: usize,
+    window: Duration,
+    sent: VecDeque<Instant>,
+}
+
+impl RateLimit {
+    fn new(max: usize, window: Duration) -> Self {
+        Self { max: max.max(1), window, sent: VecDeque::new() }
+    }
+
+    // How long to wait before the next message can go out
+    fn wait(&mut self, now: Instant) -> Duration {
+        while self.sent.front().map(|t| now.saturating_duration_since(*t) >= self.window).unwrap_or(false) {
+            self.sent.pop_front();
+        }
+
+        match self.sent.len() < self.max {
+            true => Duration::ZERO,
+            false => self.window - now.saturating_duration_since(self.sent[0]),
+        }
+    }
+
+    fn record(&mut self, now: Instant) {
+        self.sent.push_back(now);
+    }
+}
+
+fn truncate(text: &str) -> &str {
+    match text.char_indices().nth(MAX_LEN) {
+        Some((i, _)) => &text[..i],
+        None => text,
+    }
+}
+
+/// Publish chat messages on the router, for it to forward to IRC.
+/// In dry run mode the messages are only logged.
+pub async fn run(mut rx: ChatReceiver, config: ChatOutConfig) {
+    let mut limit = RateLimit::new(config.max_messages, Duration::from_secs(config.per_secs));
+    let mut publisher = Publisher::new(config.channel.as_bytes());
+
+    while let Some(text) = rx.recv().await {
+        let wait = limit.wait(Instant::now());
+        if wait > Duration::ZERO {
+            log::info!("Chat rate limit, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+        limit.record(Instant::now());
+
+        let text = truncate(text.trim());
+        if config.dry_run {
+            log::info!("[dry run] say: {}", text);
+            continue;
+        }
+
+        if let Err(e) = publisher.send(text.as_bytes()).await {
+            log::error!("Dropping chat message \"{}\": {}", text, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rate_limit() {
+        let mut limit = RateLimit::new(2, Duration::from_secs(30));
+        let now = Instant::now();
+        assert_eq!(limit.wait(now), Duration::ZERO);
+        limit.record(now);
+        limit.record(now + Duration::from_secs(10));
+        assert_eq!(limit.wait(now + Duration::from_secs(20)), Duration::from_secs(10));
+        assert_eq!(limit.wait(now + Duration::from_secs(30)), Duration::ZERO);
+    }
+
+    #[test]
+    fn truncate_long_messages() {
+        let long = "ö".repeat(MAX_LEN + 10);
+        assert_eq!(truncate(&long).chars().count(), MAX_LEN);
+        assert_eq!(truncate("hi"), "hi");
+    }
+}
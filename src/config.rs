@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::display::layout::Layout;
+use crate::transformers::commands::{Action, CustomCommand};
+
+pub const CONFIG_PATH: &str = "witchbox.json";
+
+/// Everything in the config is optional.
+/// Anything left out uses the defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub alerts: AlertConfig,
+    pub follows: FollowConfig,
+    pub chat_out: ChatOutConfig,
+    pub polls: PollConfig,
+    pub tts: TtsConfig,
+    pub shoutouts: ShoutoutConfig,
+    pub viewers: ViewerConfig,
+    /// Chat commands, by name without the `!`
+    pub commands: BTreeMap<String, CustomCommand>,
+    /// Channel point rewards, by title, e.g:
+    /// "ask me anything": [{ "type": "alert", "text": "{user} asks: {input}" }]
+    /// These win over the built in rewards.
+    pub rewards: BTreeMap<String, Vec<Action>>,
+    /// Name of the layout to start with
+    pub layout: String,
+    pub layouts: BTreeMap<String, Layout>,
+    /// Markdown file shown in the todo pane
+    pub todo_path: PathBuf,
+    /// Where `!quote add` keeps the quotes
+    pub quotes_path: PathBuf,
+    /// Where redemptions are tracked until fulfilled or rejected
+    pub redemptions_path: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            alerts: AlertConfig::default(),
+            follows: FollowConfig::default(),
+            chat_out: ChatOutConfig::default(),
+            polls: PollConfig::default(),
+            tts: TtsConfig::default(),
+            shoutouts: ShoutoutConfig::default(),
+            viewers: ViewerConfig::default(),
+            commands: BTreeMap::new(),
+            rewards: BTreeMap::new(),
+            layout: "default".to_string(),
+            layouts: [("default".to_string(), Layout::default()), ("redemptions".to_string(), Layout::redemptions())]
+                .into_iter()
+                .collect(),
+            todo_path: PathBuf::from("/home/togglebit/wiki/todo.md"),
+            quotes_path: PathBuf::from("quotes.json"),
+            redemptions_path: PathBuf::from("redemptions.json"),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            log::info!("No {}, using the default config", path.display());
+            return Ok(Self::default());
+        }
+
+        let raw = read_to_string(path)?;
+        let config: Self =
+            serde_json::from_str(&raw).map_err(|e| anyhow!("Invalid config {}: {}", path.display(), e))?;
+
+        if !config.layouts.contains_key(&config.layout) {
+            return Err(anyhow!("No layout called \"{}\" in {}", config.layout, path.display()));
+        }
+
+        Ok(config)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Alerts -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    /// Quiet time between two alerts
+    pub min_gap_ms: u64,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self { min_gap_ms: 1000 }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Follows -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FollowConfig {
+    /// This many follows within `burst_secs` is a follow bot wave
+    pub burst_count: usize,
+    pub burst_secs: u64,
+    /// Ignore repeated follows from the same user within this many seconds
+    pub dedupe_secs: u64,
+}
+
+impl Default for FollowConfig {
+    fn default() -> Self {
+        Self {
+            burst_count: 10,
+            burst_secs: 10,
+            dedupe_secs: 60 * 60 * 24,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Polls -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PollConfig {
+    /// How long a `!poll` stays open, unless ended with `!poll end`
+    pub duration_secs: u64,
+    /// How long the final result stays up
+    pub result_secs: u64,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self { duration_secs: 120, result_secs: 30 }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Text to speech -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TtsConfig {
+    pub enabled: bool,
+    /// Reads text on stdin and writes a wav to stdout.
+    /// `{voice}` is replaced with the voice, e.g. for piper:
+    /// ["piper", "--model", "{voice}", "--output_file", "/dev/stdout"]
+    pub command: Vec<String>,
+    pub default_voice: String,
+    /// Rewards to read the user input of, by title, with the voice to use
+    pub rewards: BTreeMap<String, String>,
+    /// Read out cheer messages of at least this many bits
+    pub bits_min: usize,
+    /// Longer messages are cut off
+    pub max_len: usize,
+    /// Messages with any of these are never read out
+    pub blocked_words: Vec<String>,
+    /// Speech waiting to be played, anything beyond this is dropped
+    pub queue_max: usize,
+}
+
+impl Default for TtsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: ["espeak-ng", "--stdin", "--stdout", "-v", "{voice}"].iter().map(|s| s.to_string()).collect(),
+            default_voice: "en".to_string(),
+            rewards: BTreeMap::new(),
+            bits_min: 100,
+            max_len: 200,
+            blocked_words: vec![],
+            queue_max: 10,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Shoutouts -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShoutoutConfig {
+    /// Prompt for a shoutout when any of these first chat in a session
+    pub streamers: Vec<String>,
+    /// `{user}` is replaced with the name, `{login}` with the lowercase name
+    pub message: String,
+}
+
+impl Default for ShoutoutConfig {
+    fn default() -> Self {
+        Self { streamers: vec![], message: "Go check out {user} over at https://twitch.tv/{login}".to_string() }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Viewers -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ViewerConfig {
+    /// The viewer database
+    pub path: PathBuf,
+    /// Hours away before a viewer is welcomed back
+    pub away_hours: i64,
+    /// Banner for returning viewers, `{nick}` and `{last_seen}` are replaced
+    pub welcome_back: Option<String>,
+    /// Also say the welcome back in Twitch chat
+    pub greet_in_chat: bool,
+}
+
+impl Default for ViewerConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("viewers.db"),
+            away_hours: 12,
+            welcome_back: Some("Welcome back {nick}!".to_string()),
+            greet_in_chat: false,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Outbound chat -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ChatOutConfig {
+    /// Router channel that gets forwarded to IRC
+    pub channel: String,
+    /// Twitch allows 20 messages per 30 seconds (100 as a mod)
+    pub max_messages: usize,
+    pub per_secs: u64,
+    /// Log messages instead of sending them
+    pub dry_run: bool,
+    /// Said after follows, `{names}` is replaced with who followed.
+    /// Off unless set, e.g. "Thank you for the follow {names}!"
+    pub follow_thanks: Option<String>,
+    /// Said when raided, `{name}` and `{viewers}` are replaced.
+    /// Off unless set, e.g. "Welcome raiders from {name}!"
+    pub raid_welcome: Option<String>,
+}
+
+impl Default for ChatOutConfig {
+    fn default() -> Self {
+        Self {
+            channel: "chat-out".to_string(),
+            max_messages: 20,
+            per_secs: 30,
+            dry_run: false,
+            follow_thanks: None,
+            raid_welcome: None,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use tinyroute::client::{connect, ClientMessage, TcpClient};
+
+/// Control commands are published on this channel,
+/// either by `witchbox2 <command>` or by anything else on the router.
+pub const CHANNEL: &[u8] = b"witchbox";
+
+#[derive(Debug, Clone)]
+pub enum Control {
+    Credits,
+    /// Hold back follow alerts, e.g. while being raided
+    RaidMode(bool),
+    /// Switch to a named layout
+    Layout(String),
+}
+
+impl Control {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        match std::str::from_utf8(bytes).ok()?.trim() {
+            "credits" => Some(Control::Credits),
+            "raidmode on" => Some(Control::RaidMode(true)),
+            "raidmode off" => Some(Control::RaidMode(false)),
+            cmd => match cmd.strip_prefix("layout ") {
+                Some(name) => Some(Control::Layout(name.trim().to_string())),
+                None => None,
+            },
+        }
+    }
+
+    fn payload(&self) -> String {
+        match self {
+            Control::Credits => "credits".to_string(),
+            Control::RaidMode(true) => "raidmode on".to_string(),
+            Control::RaidMode(false) => "raidmode off".to_string(),
+            Control::Layout(name) => format!("layout {}", name),
+        }
+    }
+}
+
+pub async fn send(control: Control) {
+    let tcp_client = match TcpClient::connect("127.0.0.1:6000").await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Failed to connect to the router: {}", e);
+            return;
+        }
+    };
+    let (tx, _rx) = connect(tcp_client, None);
+
+    let _ = tx.send(ClientMessage::channel_payload(CHANNEL, control.payload().as_bytes()));
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+}
@@ -0,0 +1,438 @@
+// -----------------------------------------------------------------------------
+//     - Animation files -
+//
+//     Version 1 has no version line, four headers in a fixed order
+//     and then frames of `height` lines each:
+//
+//         height: 7
+//         width: 39
+//         ticks: 3
+//         repeat: false
+//
+//     Version 2 starts with `version: 2`, followed by the same headers
+//     in any order and an optional `loop: <segment>`.
+//     Every frame starts with a marker, and can have a colour mask:
+//
+//         --- frame ticks=5 segment=idle
+//         <height lines>
+//         --- mask
+//         <up to height lines of colour codes>
+//
+//     Mask codes are r, g, y, b, m, c and w. A space or a dot is uncoloured.
+//     Frames can contain placeholders like `{name}`, filled in when played.
+// -----------------------------------------------------------------------------
+use std::fmt;
+use std::fs::read_to_string;
+use std::io;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::FromStr;
+
+use anathema::Color;
+use unicode_width::UnicodeWidthStr;
+
+pub const CURRENT_VERSION: usize = 2;
+
+pub fn mask_color(code: char) -> Option<Color> {
+    match code {
+        'r' => Some(Color::Red),
+        'g' => Some(Color::Green),
+        'y' => Some(Color::Yellow),
+        'b' => Some(Color::Blue),
+        'm' => Some(Color::Magenta),
+        'c' => Some(Color::Cyan),
+        'w' => Some(Color::White),
+        _ => None,
+    }
+}
+
+fn is_mask_code(code: char) -> bool {
+    code == ' ' || code == '.' || mask_color(code).is_some()
+}
+
+// -----------------------------------------------------------------------------
+//     - Errors -
+// -----------------------------------------------------------------------------
+#[derive(Debug)]
+pub enum AnimError {
+    Io(io::Error),
+    UnsupportedVersion(usize),
+    MissingHeader(&'static str),
+    InvalidHeader { line: usize, text: String },
+    ZeroValue(&'static str),
+    InvalidMarker { line: usize, text: String },
+    DataBeforeFrame { line: usize },
+    FrameHeight { frame: usize, lines: usize, height: usize },
+    MaskHeight { frame: usize, height: usize },
+    UnknownColor { line: usize, code: char },
+    UnknownSegment(String),
+    NoFrames,
+}
+
+impl fmt::Display for AnimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnimError::Io(e) => write!(f, "{}", e),
+            AnimError::UnsupportedVersion(v) => {
+                write!(f, "version {} is not supported (newest is {})", v, CURRENT_VERSION)
+            }
+            AnimError::MissingHeader(key) => write!(f, "missing header `{}`", key),
+            AnimError::InvalidHeader { line, text } => write!(f, "line {}: invalid header `{}`", line, text),
+            AnimError::ZeroValue(key) => write!(f, "`{}` has to be more than zero", key),
+            AnimError::InvalidMarker { line, text } => write!(f, "line {}: invalid marker `{}`", line, text),
+            AnimError::DataBeforeFrame { line } => write!(f, "line {}: frame data before the first `--- frame`", line),
+            AnimError::FrameHeight { frame, lines, height } => {
+                write!(f, "frame {} has {} lines but the height is {}", frame, lines, height)
+            }
+            AnimError::MaskHeight { frame, height } => {
+                write!(f, "the mask for frame {} is taller than the height ({})", frame, height)
+            }
+            AnimError::UnknownColor { line, code } => write!(f, "line {}: unknown colour `{}`", line, code),
+            AnimError::UnknownSegment(name) => write!(f, "no segment called `{}` to loop", name),
+            AnimError::NoFrames => write!(f, "there are no frames"),
+        }
+    }
+}
+
+impl std::error::Error for AnimError {}
+
+impl From<io::Error> for AnimError {
+    fn from(e: io::Error) -> Self {
+        AnimError::Io(e)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Frame -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub lines: Vec<String>,
+    pub mask: Vec<Vec<char>>,
+    pub ticks: usize,
+    pub segment: Option<String>,
+}
+
+impl Frame {
+    fn new(ticks: usize) -> Self {
+        Self { lines: Vec::new(), mask: Vec::new(), ticks, segment: None }
+    }
+
+    pub fn mask_code(&self, x: usize, y: usize) -> char {
+        self.mask.get(y).and_then(|l| l.get(x)).copied().unwrap_or(' ')
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Animation file -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct AnimFile {
+    pub version: usize,
+    pub height: usize,
+    pub width: usize,
+    pub ticks: usize,
+    pub repeat: bool,
+    pub loop_segment: Option<String>,
+    /// Frame to continue from when the last frame is done
+    pub loop_start: Option<usize>,
+    pub frames: Vec<Frame>,
+}
+
+type NumberedLines<'a> = Peekable<std::iter::Enumerate<std::str::Lines<'a>>>;
+
+fn parse_value<T: FromStr>(index: usize, line: &str, value: &str) -> Result<T, AnimError> {
+    value.trim().parse().map_err(|_| AnimError::InvalidHeader { line: index + 1, text: line.to_string() })
+}
+
+// Version 1 headers have a fixed order
+fn next_header<T: FromStr>(lines: &mut NumberedLines<'_>, key: &'static str) -> Result<T, AnimError> {
+    let (index, line) = lines.next().ok_or(AnimError::MissingHeader(key))?;
+    match line.split_once(':') {
+        Some((k, value)) if k.trim() == key => parse_value(index, line, value),
+        _ => Err(AnimError::MissingHeader(key)),
+    }
+}
+
+impl AnimFile {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AnimError> {
+        let src = read_to_string(path)?;
+        Self::parse(&src)
+    }
+
+    pub fn parse(src: &str) -> Result<Self, AnimError> {
+        let mut lines = src.lines().enumerate().peekable();
+
+        let version: usize = match lines.peek() {
+            Some((_, line)) if line.starts_with("version:") => next_header(&mut lines, "version")?,
+            _ => 1,
+        };
+
+        let anim = match version {
+            1 => Self::parse_v1(lines)?,
+            2 => Self::parse_v2(lines)?,
+            v => return Err(AnimError::UnsupportedVersion(v)),
+        };
+
+        if anim.frames.is_empty() {
+            return Err(AnimError::NoFrames);
+        }
+
+        Ok(anim)
+    }
+
+    fn parse_v1(mut lines: NumberedLines<'_>) -> Result<Self, AnimError> {
+        let height: usize = next_header(&mut lines, "height")?;
+        let width: usize = next_header(&mut lines, "width")?;
+        let ticks: usize = next_header(&mut lines, "ticks")?;
+        let repeat: bool = next_header(&mut lines, "repeat")?;
+        check_sizes(height, width, ticks)?;
+
+        let frame_lines = lines.map(|(_, l)| l.to_string()).collect::<Vec<_>>();
+        let mut frames = vec![];
+        for (i, chunk) in frame_lines.chunks(height).enumerate() {
+            if chunk.len() != height {
+                return Err(AnimError::FrameHeight { frame: i, lines: chunk.len(), height });
+            }
+            let mut frame = Frame::new(ticks);
+            frame.lines = chunk.to_vec();
+            frames.push(frame);
+        }
+
+        let loop_start = repeat.then_some(0);
+        Ok(Self { version: 1, height, width, ticks, repeat, loop_segment: None, loop_start, frames })
+    }
+
+    fn parse_v2(mut lines: NumberedLines<'_>) -> Result<Self, AnimError> {
+        let mut height: Option<usize> = None;
+        let mut width: Option<usize> = None;
+        let mut ticks: Option<usize> = None;
+        let mut repeat = false;
+        let mut loop_segment = None;
+
+        // Headers
+        while let Some((index, line)) = lines.next_if(|(_, l)| !l.starts_with("---")) {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let invalid = || AnimError::InvalidHeader { line: index + 1, text: line.to_string() };
+            let (key, value) = line.split_once(':').ok_or_else(invalid)?;
+            match key.trim() {
+                "height" => height = Some(parse_value(index, line, value)?),
+                "width" => width = Some(parse_value(index, line, value)?),
+                "ticks" => ticks = Some(parse_value(index, line, value)?),
+                "repeat" => repeat = parse_value(index, line, value)?,
+                "loop" => loop_segment = Some(value.trim().to_string()),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let height = height.ok_or(AnimError::MissingHeader("height"))?;
+        let width = width.ok_or(AnimError::MissingHeader("width"))?;
+        let ticks = ticks.ok_or(AnimError::MissingHeader("ticks"))?;
+        check_sizes(height, width, ticks)?;
+
+        // Frames
+        let mut frames: Vec<Frame> = vec![];
+        let mut in_mask = false;
+        for (index, line) in lines {
+            if let Some(marker) = line.strip_prefix("---") {
+                let invalid = || AnimError::InvalidMarker { line: index + 1, text: line.to_string() };
+                let mut parts = marker.split_whitespace();
+                match parts.next() {
+                    Some("frame") => {
+                        check_frame_height(&frames, height)?;
+                        let mut frame = Frame::new(ticks);
+                        for part in parts {
+                            match part.split_once('=') {
+                                Some(("ticks", value)) => {
+                                    frame.ticks = value.parse().map_err(|_| invalid())?;
+                                    if frame.ticks == 0 {
+                                        return Err(AnimError::ZeroValue("ticks"));
+                                    }
+                                }
+                                Some(("segment", name)) => frame.segment = Some(name.to_string()),
+                                _ => return Err(invalid()),
+                            }
+                        }
+                        frames.push(frame);
+                        in_mask = false;
+                    }
+                    Some("mask") if !frames.is_empty() => in_mask = true,
+                    _ => return Err(invalid()),
+                }
+                continue;
+            }
+
+            let frame_index = frames.len().saturating_sub(1);
+            let frame = frames.last_mut().ok_or(AnimError::DataBeforeFrame { line: index + 1 })?;
+            match in_mask {
+                true => {
+                    if frame.mask.len() == height {
+                        return Err(AnimError::MaskHeight { frame: frame_index, height });
+                    }
+                    if let Some(code) = line.chars().find(|c| !is_mask_code(*c)) {
+                        return Err(AnimError::UnknownColor { line: index + 1, code });
+                    }
+                    frame.mask.push(line.chars().collect());
+                }
+                false => frame.lines.push(line.to_string()),
+            }
+        }
+        check_frame_height(&frames, height)?;
+
+        let loop_start = match &loop_segment {
+            Some(name) => Some(
+                frames
+                    .iter()
+                    .position(|f| f.segment.as_ref() == Some(name))
+                    .ok_or_else(|| AnimError::UnknownSegment(name.clone()))?,
+            ),
+            None => repeat.then_some(0),
+        };
+
+        Ok(Self { version: 2, height, width, ticks, repeat, loop_segment, loop_start, frames })
+    }
+
+    /// Replace every `{key}` in the frames with its value.
+    pub fn fill_placeholders(&mut self, params: &[(&str, &str)]) {
+        for (key, value) in params {
+            let placeholder = format!("{{{}}}", key);
+            self.frames
+                .iter_mut()
+                .flat_map(|f| f.lines.iter_mut())
+                .filter(|l| l.contains(&placeholder))
+                .for_each(|l| *l = l.replace(&placeholder, value));
+        }
+    }
+}
+
+impl AnimFile {
+    /// Problems that don't stop the animation from playing,
+    /// but will most likely make it look wrong.
+    pub fn lint(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        let mut widest = 0;
+        for (i, frame) in self.frames.iter().enumerate() {
+            for (y, line) in frame.lines.iter().enumerate() {
+                let width = line.width();
+                widest = widest.max(width);
+                if width > self.width {
+                    problems.push(format!("frame {} line {} is {} wide, the width is {}", i, y + 1, width, self.width));
+                }
+            }
+
+            for (y, mask_line) in frame.mask.iter().enumerate() {
+                let line_len = frame.lines.get(y).map(|l| l.chars().count()).unwrap_or(0);
+                if mask_line.len() > line_len {
+                    problems.push(format!("frame {} mask line {} is wider than the frame line", i, y + 1));
+                }
+            }
+
+            if let Some(name) = &frame.segment {
+                if self.frames[..i].iter().any(|f| f.segment.as_ref() == Some(name)) {
+                    problems.push(format!("frame {} starts segment `{}` again", i, name));
+                }
+            }
+        }
+
+        if widest < self.width {
+            problems.push(format!("the widest line is {} but the width is {}", widest, self.width));
+        }
+
+        if self.repeat && self.loop_segment.is_some() {
+            problems.push("both `repeat` and `loop` are set, `loop` wins".to_string());
+        }
+
+        problems
+    }
+}
+
+fn check_sizes(height: usize, width: usize, ticks: usize) -> Result<(), AnimError> {
+    match (height, width, ticks) {
+        (0, _, _) => Err(AnimError::ZeroValue("height")),
+        (_, 0, _) => Err(AnimError::ZeroValue("width")),
+        (_, _, 0) => Err(AnimError::ZeroValue("ticks")),
+        _ => Ok(()),
+    }
+}
+
+fn check_frame_height(frames: &[Frame], height: usize) -> Result<(), AnimError> {
+    match frames.last() {
+        Some(frame) if frame.lines.len() != height => {
+            Err(AnimError::FrameHeight { frame: frames.len() - 1, lines: frame.lines.len(), height })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const V2: &str = "version: 2
+height: 2
+width: 3
+ticks: 4
+loop: idle
+--- frame ticks=1
+abc
+{n}
+--- mask
+r.g
+--- frame segment=idle
+def
+ghi
+";
+
+    #[test]
+    fn parse_v1() {
+        let anim = AnimFile::parse("height: 1\nwidth: 3\nticks: 2\nrepeat: true\nabc\ndef").unwrap();
+        assert_eq!(anim.frames.len(), 2);
+        assert_eq!(anim.loop_start, Some(0));
+    }
+
+    #[test]
+    fn parse_v2() {
+        let mut anim = AnimFile::parse(V2).unwrap();
+        assert_eq!(anim.frames[0].ticks, 1);
+        assert_eq!(anim.frames[1].ticks, 4);
+        assert_eq!(anim.frames[0].mask_code(2, 0), 'g');
+        assert_eq!(anim.loop_start, Some(1));
+
+        anim.fill_placeholders(&[("n", "xy")]);
+        assert_eq!(anim.frames[0].lines[1], "xy");
+    }
+
+    #[test]
+    fn short_frame() {
+        let err = AnimFile::parse("height: 2\nwidth: 3\nticks: 2\nrepeat: true\nabc\ndef\nghi").unwrap_err();
+        assert!(matches!(err, AnimError::FrameHeight { frame: 1, lines: 1, height: 2 }));
+    }
+
+    #[test]
+    fn unknown_colour() {
+        let src = V2.replace("r.g", "r.x");
+        let err = AnimFile::parse(&src).unwrap_err();
+        assert!(matches!(err, AnimError::UnknownColor { line: 10, code: 'x' }));
+    }
+
+    #[test]
+    fn missing_segment() {
+        let src = V2.replace("loop: idle", "loop: nope");
+        assert!(matches!(AnimFile::parse(&src).unwrap_err(), AnimError::UnknownSegment(_)));
+    }
+
+    #[test]
+    fn lint_valid_v1() {
+        let anim = AnimFile::parse("height: 1\nwidth: 3\nticks: 2\nrepeat: true\nabc\ndef").unwrap();
+        assert!(anim.lint().is_empty());
+    }
+
+    #[test]
+    fn newer_version() {
+        assert!(matches!(AnimFile::parse("version: 3\n").unwrap_err(), AnimError::UnsupportedVersion(3)));
+    }
+}
@@ -1,47 +1,140 @@
-use std::fs::read_to_string;
 use std::path::Path;
 use std::time::{Duration, Instant};
 
 use anathema::{split, Color, Colors, Lines, Pos, Size, Window};
 use anyhow::Result;
 use rand::prelude::*;
+use serde::Deserialize;
 use unicode_width::UnicodeWidthStr;
 
-use crate::display::models::Tier;
-use crate::display::random_color;
-
-const ANIMATIONS: &[&str] = &[
-    "animations/prime.txt",
-    "animations/prime2.txt",
-    "animations/bender.txt",
-];
-
-pub fn get_anim_src(tier: Tier) -> &'static str {
-    let mut rng = thread_rng();
-    match tier {
-        Tier::Prime => ANIMATIONS[..2].choose(&mut rng).unwrap(),
-        Tier::One => ANIMATIONS[2..3].choose(&mut rng).unwrap(),
-        Tier::Two => ANIMATIONS[2..3].choose(&mut rng).unwrap(),
-        Tier::Three => ANIMATIONS[2..3].choose(&mut rng).unwrap(),
-        Tier::Unknown => ANIMATIONS[2..3].choose(&mut rng).unwrap(),
-    }
-}
+use super::anim_file::{mask_color, AnimError, AnimFile, Frame};
+use super::easing::Easing;
+use crate::display::{random_color, DARK_GREY, GREY};
+
+const RAINBOW: [Color; 6] = [Color::Red, Color::Yellow, Color::Green, Color::Cyan, Color::Blue, Color::Magenta];
+const FADE_RAMP: [Color; 3] = [Color::White, GREY, DARK_GREY];
 
 #[derive(Debug, Copy, Clone)]
 pub struct Char {
     pub c: char,
     pub current_pos: Pos,
     pub color: Color,
+    visible: bool,
     start: Pos,
+    // Where the current phase started moving from
+    from: Pos,
     dest: Pos,
 }
 
+// -----------------------------------------------------------------------------
+//     - Text effects -
+// -----------------------------------------------------------------------------
+/// How the characters enter
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Animation {
+    Scatter,
+    HorzSlide,
+    VertSlide,
+    Typewriter,
+}
+
+/// What the characters do while waiting
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Idle {
+    Still,
+    Wave,
+}
+
+/// How the characters leave
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Exit {
+    Reverse,
+    Explode,
+    Fade,
+    Typewriter,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Coloring {
+    Single,
+    Rainbow,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+pub struct TextEffect {
+    pub enter: Animation,
+    #[serde(default = "TextEffect::default_idle")]
+    pub idle: Idle,
+    #[serde(default = "TextEffect::default_exit")]
+    pub exit: Exit,
+    #[serde(default = "TextEffect::default_coloring")]
+    pub coloring: Coloring,
+    #[serde(default = "TextEffect::default_enter_easing")]
+    pub enter_easing: Easing,
+    #[serde(default = "TextEffect::default_exit_easing")]
+    pub exit_easing: Easing,
+    #[serde(default = "TextEffect::default_enter_ms")]
+    pub enter_ms: u64,
+    #[serde(default = "TextEffect::default_exit_ms")]
+    pub exit_ms: u64,
+}
+
+impl TextEffect {
+    fn default_enter_easing() -> Easing {
+        Easing::EaseOut
+    }
+
+    fn default_exit_easing() -> Easing {
+        Easing::EaseIn
+    }
+
+    fn default_enter_ms() -> u64 {
+        1000
+    }
+
+    fn default_exit_ms() -> u64 {
+        800
+    }
+
+    fn default_idle() -> Idle {
+        Idle::Still
+    }
+
+    fn default_exit() -> Exit {
+        Exit::Reverse
+    }
+
+    fn default_coloring() -> Coloring {
+        Coloring::Single
+    }
+}
+
+impl From<Animation> for TextEffect {
+    fn from(enter: Animation) -> Self {
+        Self {
+            enter,
+            idle: Self::default_idle(),
+            exit: Self::default_exit(),
+            coloring: Self::default_coloring(),
+            enter_easing: Self::default_enter_easing(),
+            exit_easing: Self::default_exit_easing(),
+            enter_ms: Self::default_enter_ms(),
+            exit_ms: Self::default_exit_ms(),
+        }
+    }
+}
+
 // -----------------------------------------------------------------------------
 //     - Character movement anim -
 // -----------------------------------------------------------------------------
+#[derive(Copy, Clone)]
 enum AnimState {
     In,
-    Wait(Instant),
+    Wait,
     Out,
     Done,
 }
@@ -51,12 +144,26 @@ pub struct CharAnim {
     pub is_done: bool,
     pub ttl: Duration,
     state: AnimState,
+    effect: TextEffect,
+    size: Size,
+    // Both are set on the first update rather than on creation,
+    // as the animation can sit in a queue for a while.
+    started: Option<Instant>,
+    phase_started: Option<Instant>,
 }
 
-pub enum Animation {
-    Scatter,
-    HorzSlide,
-    VertSlide,
+// How far along a phase is, from 0.0 to 1.0
+fn progress(elapsed: Duration, duration_ms: u64) -> f32 {
+    match duration_ms {
+        0 => 1.0,
+        ms => (elapsed.as_secs_f32() * 1000.0 / ms as f32).min(1.0),
+    }
+}
+
+fn lerp(from: Pos, to: Pos, t: f32) -> Pos {
+    let x = from.x + ((to.x - from.x) as f32 * t).round() as i32;
+    let y = from.y + ((to.y - from.y) as f32 * t).round() as i32;
+    Pos::new(x, y)
 }
 
 fn animation_chars(lines: Vec<&str>, size: Size, animation: Animation) -> Vec<Char> {
@@ -72,17 +179,21 @@ fn animation_chars(lines: Vec<&str>, size: Size, animation: Animation) -> Vec<Ch
             let x = x as i32;
             let y = y as i32;
             let mut rng = thread_rng();
+            let dest = Pos::new(x as i32 + dest_x, y as i32 + dest_y);
 
             let start = match animation {
                 Animation::Scatter => Pos::new(rng.gen_range(0..size.width), 1),
                 Animation::HorzSlide => Pos::new(x + size.width, y + dest_y),
                 Animation::VertSlide => Pos::new(x + dest_x, y + size.height),
+                Animation::Typewriter => dest,
             };
 
             chars.push(Char {
-                dest: Pos::new(x as i32 + dest_x, y as i32 + dest_y),
+                visible: !matches!(animation, Animation::Typewriter),
+                dest,
                 current_pos: start,
                 start,
+                from: start,
                 c,
                 color,
             });
@@ -93,72 +204,109 @@ fn animation_chars(lines: Vec<&str>, size: Size, animation: Animation) -> Vec<Ch
 }
 
 impl CharAnim {
-    pub fn new(words: &str, size: Size, animation: Animation) -> Self {
+    pub fn new(words: &str, size: Size, effect: TextEffect) -> Self {
         let lines = split(words, size.width as usize, 0, true).collect::<Vec<_>>();
 
-        let chars = animation_chars(lines, size, animation);
+        let chars = animation_chars(lines, size, effect.enter);
 
         let wpm = words.split_whitespace().count() as f32 * 0.25 + 3.0;
 
         let ttl = Duration::from_secs(wpm as u64);
-        Self { chars, is_done: false, ttl, state: AnimState::In }
+        Self {
+            chars,
+            is_done: false,
+            ttl,
+            state: AnimState::In,
+            effect,
+            size,
+            started: None,
+            phase_started: None,
+        }
+    }
+
+    fn next_phase(&mut self, state: AnimState) {
+        self.state = state;
+        self.phase_started = Some(Instant::now());
+    }
+
+    fn move_chars(&mut self, t: f32) {
+        self.chars.iter_mut().for_each(|c| c.current_pos = lerp(c.from, c.dest, t));
+    }
+
+    fn start_exit(&mut self) {
+        self.next_phase(AnimState::Out);
+        self.chars.iter_mut().for_each(|c| c.from = c.current_pos);
+
+        match self.effect.exit {
+            Exit::Reverse => self.chars.iter_mut().for_each(|c| c.dest = c.start),
+            Exit::Explode => {
+                // Send every char away from the middle of the text,
+                // far enough to leave the screen.
+                let center = Pos::new(self.size.width / 2, self.size.height / 2);
+                let distance = self.size.width.max(self.size.height);
+                let mut rng = thread_rng();
+                for c in &mut self.chars {
+                    let mut dir = c.current_pos - center;
+                    if dir.x == 0 && dir.y == 0 {
+                        dir = Pos::new(rng.gen_range(-1..=1), -1);
+                    }
+                    let scale = distance / dir.x.abs().max(dir.y.abs());
+                    c.dest = Pos::new(c.current_pos.x + dir.x * scale, c.current_pos.y + dir.y * scale);
+                }
+            }
+            Exit::Fade | Exit::Typewriter => {}
+        }
     }
 
     pub fn update(&mut self) -> Vec<Char> {
+        let now = Instant::now();
+        self.started.get_or_insert(now);
+        let elapsed = now - *self.phase_started.get_or_insert(now);
+        let char_count = self.chars.len() as f32;
+
         match self.state {
-            AnimState::In | AnimState::Out => {
-                let mut is_done = true;
-
-                let mut remove = Vec::new();
-                for (index, c) in &mut self.chars.iter_mut().enumerate() {
-                    if c.current_pos != c.dest {
-                        is_done = false;
-                        let v = (c.dest - c.current_pos).abs();
-
-                        if v.x > v.y {
-                            if c.current_pos.x > c.dest.x {
-                                c.current_pos.x -= 1;
-                            }
-                            if c.current_pos.x < c.dest.x {
-                                c.current_pos.x += 1;
-                            }
-                        } else {
-                            if c.current_pos.y > c.dest.y {
-                                c.current_pos.y -= 1;
-                            }
-                            if c.current_pos.y < c.dest.y {
-                                c.current_pos.y += 1;
-                            }
-                        }
-
-                        if c.current_pos == c.dest {
-                            remove.push(index);
-                        }
+            AnimState::In => {
+                let t = progress(elapsed, self.effect.enter_ms);
+                match self.effect.enter {
+                    Animation::Typewriter => {
+                        let shown = (t * char_count).ceil() as usize;
+                        self.chars.iter_mut().take(shown).for_each(|c| c.visible = true);
+                    }
+                    Animation::Scatter | Animation::HorzSlide | Animation::VertSlide => {
+                        self.move_chars(self.effect.enter_easing.apply(t))
                     }
                 }
 
-                // Remove every char that has reached its destination,
-                // when animating out
-                if let AnimState::Out = self.state {
-                    remove.into_iter().rev().for_each(|i| {
-                        self.chars.remove(i);
-                    });
+                if t >= 1.0 {
+                    self.next_phase(AnimState::Wait);
                 }
-
-                if is_done {
-                    match self.state {
-                        AnimState::In => {
-                            self.state = AnimState::Wait(Instant::now());
-                        }
-                        AnimState::Out => self.state = AnimState::Done,
-                        _ => unreachable!(),
-                    }
+            }
+            AnimState::Wait => {
+                if elapsed > self.ttl {
+                    self.start_exit();
                 }
             }
-            AnimState::Wait(instant) => {
-                if instant.elapsed() > self.ttl {
-                    self.state = AnimState::Out;
-                    self.chars.iter_mut().for_each(|c| c.dest = c.start);
+            AnimState::Out => {
+                let t = progress(elapsed, self.effect.exit_ms);
+                match self.effect.exit {
+                    Exit::Reverse | Exit::Explode => {
+                        self.move_chars(self.effect.exit_easing.apply(t));
+                        // Chars are gone as soon as they get where they're going
+                        self.chars.retain(|c| c.current_pos != c.dest);
+                    }
+                    Exit::Typewriter => {
+                        let shown = char_count - (t * char_count).ceil();
+                        self.chars.iter_mut().enumerate().for_each(|(i, c)| c.visible = (i as f32) < shown);
+                    }
+                    Exit::Fade => {
+                        let step = ((t * FADE_RAMP.len() as f32) as usize).min(FADE_RAMP.len() - 1);
+                        self.chars.iter_mut().for_each(|c| c.color = FADE_RAMP[step]);
+                    }
+                }
+
+                if t >= 1.0 {
+                    self.chars.clear();
+                    self.state = AnimState::Done;
                 }
             }
             AnimState::Done => {
@@ -169,17 +317,42 @@ impl CharAnim {
         self.chars.clone()
     }
 
+    fn color(&self, c: &Char) -> Color {
+        let fading = matches!((self.state, self.effect.exit), (AnimState::Out, Exit::Fade));
+        match self.effect.coloring {
+            Coloring::Rainbow if !fading => {
+                let secs = self.started.map(|s| s.elapsed().as_secs_f32()).unwrap_or(0.0);
+                let index = (c.current_pos.x + (secs * 10.0) as i32).rem_euclid(RAINBOW.len() as i32);
+                RAINBOW[index as usize]
+            }
+            Coloring::Rainbow | Coloring::Single => c.color,
+        }
+    }
+
+    fn offset(&self, c: &Char) -> Pos {
+        match (self.state, self.effect.idle) {
+            (AnimState::Wait, Idle::Wave) => {
+                let secs = self.phase_started.map(|s| s.elapsed().as_secs_f32()).unwrap_or(0.0);
+                let phase = secs * 6.0 + c.current_pos.x as f32 * 0.5;
+                Pos::new(0, phase.sin().round() as i32)
+            }
+            _ => Pos::new(0, 0),
+        }
+    }
+
     pub fn draw<T>(&mut self, window: &mut Window<T>) -> Result<()> {
         let chars = self.update();
 
-        for c in chars {
-            if !window.contains(c.current_pos) {
+        for c in chars.iter().filter(|c| c.visible) {
+            let offset = self.offset(c);
+            let pos = Pos::new(c.current_pos.x + offset.x, c.current_pos.y + offset.y);
+            if !window.contains(pos) {
                 continue;
             }
-            let color_id: i16 = c.color.into();
+            let color_id: i16 = self.color(c).into();
             let pair = Colors::get_color_pair(color_id as u32);
             window.set_color(pair)?;
-            window.add_char_at(c.current_pos, c.c)?;
+            window.add_char_at(pos, c.c)?;
         }
 
         let reset = Colors::get_color_pair(0);
@@ -203,66 +376,44 @@ pub struct FrameAnim {
     current_frame: usize,
     screen_width: usize,
     state: FrameAnimState,
-    ticks_per_frame: usize,
     current_tick: usize,
-    repeat: bool,
+    loop_start: Option<usize>,
     pub ttl: Duration,
     pub is_done: bool,
     pub height: usize,
 }
 
-fn attrib_int(input: Option<&str>, prefix: &str) -> usize {
-    input.and_then(|s| s.strip_prefix(prefix)).map(str::trim).map(str::parse::<usize>).and_then(Result::ok).unwrap_or(0)
-}
-
-fn attrib_bool(input: Option<&str>, prefix: &str) -> bool {
-    input
-        .and_then(|s| s.strip_prefix(prefix))
-        .map(str::trim)
-        .map(str::parse::<bool>)
-        .and_then(Result::ok)
-        .unwrap_or(false)
-}
-
 impl FrameAnim {
-    pub fn new(path: impl AsRef<Path>, screen_width: usize) -> Self {
-        let raw = read_to_string(path).unwrap();
-        let mut lines = raw.lines();
+    /// Check that an animation file can be played.
+    pub fn validate(path: impl AsRef<Path>) -> Result<(), AnimError> {
+        AnimFile::load(path).map(|_| ())
+    }
 
-        // let lines_per_frame = lines.next().unwrap().strip_prefix("").parse::<usize>().unwrap();
-        let lines_per_frame = attrib_int(lines.next(), "height:");
-        let frame_width = attrib_int(lines.next(), "width:");
-        let ticks = attrib_int(lines.next(), "ticks:");
-        let repeat = attrib_bool(lines.next(), "repeat:");
+    pub fn new(path: impl AsRef<Path>, screen_width: usize, params: &[(&str, &str)]) -> Result<Self, AnimError> {
+        let mut anim = AnimFile::load(path)?;
+        anim.fill_placeholders(params);
 
-        let frame_padding = if screen_width > frame_width {
+        let frame_padding = if screen_width > anim.width {
             // Pad
-            (screen_width - frame_width) / 2
+            (screen_width - anim.width) / 2
         } else {
             0
         };
 
-        // A lot of allocations here... ¬_¬
-        let frames = lines
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>()
-            .chunks(lines_per_frame)
-            .map(|l| Frame { lines: l.into_iter().map(|s| s.to_string()).collect() })
-            .collect();
-
-        Self {
+        let inst = Self {
             frame_padding,
-            frames,
+            current_tick: anim.frames[0].ticks,
+            frames: anim.frames,
             current_frame: 0,
             screen_width,
             ttl: Duration::from_secs(4),
             is_done: false,
             state: FrameAnimState::NotStarted,
-            height: lines_per_frame,
-            ticks_per_frame: ticks,
-            current_tick: ticks,
-            repeat,
-        }
+            height: anim.height,
+            loop_start: anim.loop_start,
+        };
+
+        Ok(inst)
     }
 
     pub fn update(&mut self) -> Lines {
@@ -283,39 +434,53 @@ impl FrameAnim {
             return lines;
         }
 
-        for frame_line in &self.frames[self.current_frame].lines {
+        let frame = &self.frames[self.current_frame];
+        for (y, frame_line) in frame.lines.iter().enumerate() {
             lines.pad(1); // pad one to avoid drawing over the border
-                          // let mut line = Line::new();
 
-            // line.pad(1); // pad one to avoid drawing over the border
             if self.frame_padding > 0 {
                 lines.pad(self.frame_padding);
             }
 
-            match frame_line.width() > self.screen_width {
-                true => lines.push_str(&frame_line[..self.screen_width], true),
-                false => lines.push_str(frame_line, true),
+            // Push the line in runs of the same colour
+            let mut run_start = 0;
+            let mut run_end = 0;
+            let mut run_code = frame.mask_code(0, y);
+            for (x, (i, c)) in frame_line.char_indices().take(self.screen_width).enumerate() {
+                let code = frame.mask_code(x, y);
+                if code != run_code {
+                    push_run(&mut lines, &frame_line[run_start..i], run_code);
+                    run_start = i;
+                    run_code = code;
+                }
+                run_end = i + c.len_utf8();
             }
+            push_run(&mut lines, &frame_line[run_start..run_end], run_code);
+            lines.reset_color();
             lines.force_new_line();
         }
 
         self.current_tick -= 1;
         if self.current_tick == 0 {
-            self.current_tick = self.ticks_per_frame;
             self.current_frame += 1;
-        }
-        if self.current_frame == self.frames.len() {
-            if self.repeat {
-                self.current_frame = 0;
-            } else {
-                self.current_frame = self.frames.len() - 1;
+            if self.current_frame == self.frames.len() {
+                self.current_frame = self.loop_start.unwrap_or(self.frames.len() - 1);
             }
+            self.current_tick = self.frames[self.current_frame].ticks;
         }
 
         lines
     }
 }
 
-struct Frame {
-    lines: Vec<String>,
+fn push_run<'a>(lines: &mut Lines<'a>, run: &'a str, code: char) {
+    if run.is_empty() {
+        return;
+    }
+
+    match mask_color(code).map(Colors::init_fg) {
+        Some(Ok(col)) => lines.color(col),
+        _ => lines.reset_color(),
+    }
+    lines.push_str(run, true);
 }
@@ -1,10 +1,10 @@
 use std::fs::read_to_string;
 
-use anathema::{Attribute, Colors, Input, Lines, Pos, Size, Sub, Window};
+use anathema::{Attribute, Color, Colors, Input, Lines, Pos, Size, Sub, Window};
 use anyhow::Result;
 use unicode_width::UnicodeWidthStr;
 
-use super::models::DisplayMessage;
+use super::models::{DisplayMessage, Highlight};
 use super::random_color;
 
 const BORDER_1: &str =
@@ -73,6 +73,26 @@ impl ChatDisplay {
                     lines.pad(1);
                     lines.reset_color();
 
+                    match msg.highlight {
+                        Some(Highlight::FirstTime) => {
+                            if let Ok(col) = Colors::init_fg(Color::Green) {
+                                lines.color(col);
+                            }
+                            lines.push_str("★", true);
+                            lines.pad(1);
+                            lines.reset_color();
+                        }
+                        Some(Highlight::Returning) => {
+                            if let Ok(col) = Colors::init_fg(Color::Cyan) {
+                                lines.color(col);
+                            }
+                            lines.push_str("↺", true);
+                            lines.pad(1);
+                            lines.reset_color();
+                        }
+                        None => {}
+                    }
+
                     if let Some(ref col) = msg.color {
                         let res = colors.from_hex(col).and_then(Colors::init_fg);
                         if let Ok(col) = res {
@@ -132,6 +152,16 @@ impl ChatDisplay {
                 | DisplayMessage::ClearChat
                 | DisplayMessage::Sub(_, _)
                 | DisplayMessage::TodoUpdate(_)
+                | DisplayMessage::TodoUnavailable(_)
+                | DisplayMessage::Credits(_)
+                | DisplayMessage::Layout(_)
+                | DisplayMessage::Poll(_)
+                | DisplayMessage::Speak(_)
+                | DisplayMessage::Redemption(_)
+                | DisplayMessage::Raid(..)
+                | DisplayMessage::Bits(..)
+                | DisplayMessage::GiftBomb(..)
+                | DisplayMessage::GiftBombRecipient { .. }
                 | DisplayMessage::ChannelPoints(_) => {}
             };
         }
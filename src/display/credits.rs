@@ -0,0 +1,89 @@
+use anathema::{Color, Colors, Pos, Size, Window};
+use anyhow::Result;
+use unicode_width::UnicodeWidthStr;
+
+use super::animation::{Animation, CharAnim};
+use crate::stats::Stats;
+
+// Number of ticks before the roll moves up one row
+const TICKS_PER_ROW: usize = 4;
+
+struct CreditLine {
+    text: String,
+    color: Option<Color>,
+}
+
+impl CreditLine {
+    fn plain(text: impl Into<String>) -> Self {
+        Self { text: text.into(), color: None }
+    }
+
+    fn header(text: impl Into<String>) -> Self {
+        Self { text: text.into(), color: Some(Color::Cyan) }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Credits roll -
+// -----------------------------------------------------------------------------
+pub struct CreditsRoll {
+    title: CharAnim,
+    lines: Vec<CreditLine>,
+    offset: i32,
+    current_tick: usize,
+    pub is_done: bool,
+}
+
+impl CreditsRoll {
+    pub fn new(stats: &Stats, size: Size) -> Self {
+        let title = CharAnim::new("~ Thanks for watching ~", size, Animation::Scatter.into());
+        let mut lines = vec![CreditLine::plain(stats.started.clone()), CreditLine::plain("")];
+
+        for section in stats.sections() {
+            lines.push(CreditLine::plain(""));
+            lines.push(CreditLine::header(section.title));
+            lines.extend(section.entries.into_iter().map(CreditLine::plain));
+        }
+
+        Self { title, lines, offset: 0, current_tick: TICKS_PER_ROW, is_done: false }
+    }
+
+    pub fn draw<T>(&mut self, window: &mut Window<T>) -> Result<()> {
+        // Play the title before rolling
+        if !self.title.is_done {
+            return self.title.draw(window);
+        }
+
+        let size = window.size();
+        let top = size.height - self.offset;
+
+        // Everything has scrolled off the top
+        if top + (self.lines.len() as i32) < 0 {
+            self.is_done = true;
+            return Ok(());
+        }
+
+        for (i, line) in self.lines.iter().enumerate() {
+            let y = top + i as i32;
+            if y < 0 || y >= size.height {
+                continue;
+            }
+
+            let x = (size.width / 2 - line.text.width() as i32 / 2).max(0);
+            let color_id: i16 = line.color.unwrap_or(Color::White).into();
+            window.set_color(Colors::get_color_pair(color_id as u32))?;
+            window.print_at(Pos::new(x, y), &line.text)?;
+        }
+
+        let reset = Colors::get_color_pair(7);
+        window.set_color(reset)?;
+
+        self.current_tick -= 1;
+        if self.current_tick == 0 {
+            self.current_tick = TICKS_PER_ROW;
+            self.offset += 1;
+        }
+
+        Ok(())
+    }
+}
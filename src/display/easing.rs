@@ -0,0 +1,74 @@
+use serde::Deserialize;
+
+/// Easing curves, mapping progress from 0.0 to 1.0
+/// onto how far along the movement should be.
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    Bounce,
+    Overshoot,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => match t < 0.5 {
+                true => 4.0 * t * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            },
+            Easing::Bounce => bounce(t),
+            Easing::Overshoot => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+        }
+    }
+}
+
+fn bounce(t: f32) -> f32 {
+    const N1: f32 = 7.5625;
+    const D1: f32 = 2.75;
+
+    if t < 1.0 / D1 {
+        N1 * t * t
+    } else if t < 2.0 / D1 {
+        let t = t - 1.5 / D1;
+        N1 * t * t + 0.75
+    } else if t < 2.5 / D1 {
+        let t = t - 2.25 / D1;
+        N1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D1;
+        N1 * t * t + 0.984375
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALL: [Easing; 6] =
+        [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut, Easing::Bounce, Easing::Overshoot];
+
+    #[test]
+    fn start_and_end() {
+        for easing in ALL {
+            assert!(easing.apply(0.0).abs() < 0.001, "{:?} doesn't start at 0", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 0.001, "{:?} doesn't end at 1", easing);
+        }
+    }
+
+    #[test]
+    fn overshoot_goes_past_the_end() {
+        assert!(Easing::Overshoot.apply(0.8) > 1.0);
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::animation::{Animation, TextEffect};
+
+const EFFECTS: &str = "effects.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectEvent {
+    Follow,
+    FollowGroup,
+    Redemption,
+    Bits,
+    Sub,
+    Raid,
+    PollResult,
+}
+
+impl EffectEvent {
+    // What every event looked like before effects were configurable
+    fn default_effect(&self) -> TextEffect {
+        match self {
+            EffectEvent::Follow => Animation::HorzSlide.into(),
+            EffectEvent::FollowGroup | EffectEvent::PollResult => Animation::VertSlide.into(),
+            EffectEvent::Redemption | EffectEvent::Bits | EffectEvent::Sub | EffectEvent::Raid => {
+                Animation::Scatter.into()
+            }
+        }
+    }
+}
+
+/// Text effects per event type, from `effects.json` in the animation directory.
+/// Events that aren't listed use their default effect.
+#[derive(Debug, Clone, Default)]
+pub struct TextEffects {
+    effects: HashMap<EffectEvent, TextEffect>,
+}
+
+impl TextEffects {
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let path = dir.as_ref().join(EFFECTS);
+        if !path.exists() {
+            log::info!("No {}, using default text effects", path.display());
+            return Ok(Self::default());
+        }
+
+        let effects = serde_json::from_str(&read_to_string(path)?)?;
+        Ok(Self { effects })
+    }
+
+    pub fn get(&self, event: EffectEvent) -> TextEffect {
+        self.effects.get(&event).copied().unwrap_or_else(|| event.default_effect())
+    }
+}
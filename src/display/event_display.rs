@@ -1,27 +1,43 @@
 use std::collections::VecDeque;
 use std::fmt::Write;
+use std::time::{Duration, Instant};
 
-use anathema::{Color, Colors, Lines, Pos, Size, Sub, Window};
+use anathema::{Attribute, Color, Colors, Lines, Pos, Size, Sub, Window};
 use anyhow::Result;
 use rodio::OutputStreamHandle;
 
-use super::animation::{Animation, CharAnim};
-use super::models::DisplayMessage;
+use super::animation::CharAnim;
+use super::effects::{EffectEvent, TextEffects};
+use super::models::{DisplayMessage, Poll};
 use crate::audio::SoundPlayer;
+use crate::todo::Todo;
 
 pub struct EventDisplay {
     window: Window<Sub>,
     inner_win: Window<Sub>,
     queue: VecDeque<(CharAnim, Option<String>)>,
     current: Option<CharAnim>,
-    todo: Option<String>,
     sound_player: Option<SoundPlayer>,
     output_handle: OutputStreamHandle,
+    effects: TextEffects,
+    // The poll is drawn whenever no animation is playing.
+    // A finished poll stays up until `poll_until`.
+    poll: Option<Poll>,
+    poll_until: Option<Instant>,
+    poll_result: Duration,
+    // Todo progress, drawn when idle if the layout has no todo pane
+    todo: Option<Todo>,
+    show_todo: bool,
     dirty: bool,
 }
 
 impl EventDisplay {
-    pub fn new(window: Window<Sub>, output_handle: OutputStreamHandle, todo: Option<String>) -> Result<Self> {
+    pub fn new(
+        window: Window<Sub>,
+        output_handle: OutputStreamHandle,
+        effects: TextEffects,
+        poll_result: Duration,
+    ) -> Result<Self> {
         let pos = Pos::new(1, 1);
         let parent_size = window.size();
         let size = Size::new(parent_size.width - 2, parent_size.height - 2);
@@ -31,9 +47,14 @@ impl EventDisplay {
             inner_win,
             queue: VecDeque::with_capacity(100),
             current: None,
-            todo,
             sound_player: None,
             output_handle,
+            effects,
+            poll: None,
+            poll_until: None,
+            poll_result,
+            todo: None,
+            show_todo: false,
             dirty: true,
         };
         Ok(inst)
@@ -43,30 +64,18 @@ impl EventDisplay {
         self.dirty = true;
     }
 
-    pub fn wants_update(&self) -> bool {
-        !self.queue.is_empty() || self.current.is_some()
+    /// Show the todo progress here, for layouts without a todo pane
+    pub fn show_todo(&mut self, show: bool) {
+        self.show_todo = show;
+        self.dirty = true;
     }
 
-    fn show_todo(&self) -> Result<()> {
-        let todo = match self.todo.as_ref() {
-            Some(t) => t,
-            None => return Ok(()),
-        };
-
-        self.inner_win.erase()?;
-
-        let mut lines = Lines::new(self.inner_win.size().width as usize);
-        for line in todo.lines() {
-            lines.push_str(line, true);
-            lines.force_new_line();
-        }
-        super::render_lines(lines, &self.inner_win, 0)?;
-
-        Ok(())
+    pub fn wants_update(&self) -> bool {
+        !self.queue.is_empty() || self.current.is_some()
     }
 
     fn next_frame(&mut self) -> Result<()> {
-        // mark it as dirty so we re-draw the todo if there is one
+        // mark it as dirty so the panel is cleared once the animation is done
         self.dirty = true;
 
         match self.current {
@@ -96,10 +105,14 @@ impl EventDisplay {
     pub fn handle(&mut self, msg: &DisplayMessage) -> Result<()> {
         match msg {
             DisplayMessage::ChannelPoints(points_event) => {
+                let text = match &points_event.user_input {
+                    Some(input) => format!("{}: {}\n{}", points_event.user, points_event.title, input),
+                    None => format!("{}: {}", points_event.user, points_event.title),
+                };
                 let animation = CharAnim::new(
-                    &format!("{}: {}", points_event.user, points_event.title),
+                    &text,
                     self.inner_win.size(),
-                    Animation::Scatter,
+                    self.effects.get(EffectEvent::Redemption),
                 );
                 self.queue.push_back((animation, points_event.sound_path.clone()));
             }
@@ -113,24 +126,75 @@ impl EventDisplay {
                         log::error!("Failed to write string: {}", e);
                     }
                 });
-                let anim = (followers.len() > 1).then(|| Animation::VertSlide).unwrap_or(Animation::HorzSlide);
-                let animation = CharAnim::new(&s, self.inner_win.size(), anim);
+                let event = (followers.len() > 1).then(|| EffectEvent::FollowGroup).unwrap_or(EffectEvent::Follow);
+                let animation = CharAnim::new(&s, self.inner_win.size(), self.effects.get(event));
+                self.queue.push_back((animation, Some(sound.clone())));
+            }
+            DisplayMessage::Bits(bits, sound) => {
+                let animation = CharAnim::new(
+                    &format!("{} cheered {} bits!", bits.name, bits.amount),
+                    self.inner_win.size(),
+                    self.effects.get(EffectEvent::Bits),
+                );
                 self.queue.push_back((animation, Some(sound.clone())));
             }
-            DisplayMessage::TodoUpdate(new_todo) => {
-                self.todo = Some(new_todo.clone());
+            DisplayMessage::Poll(poll) => {
+                // Twitch can end a poll more than once (completed, then archived)
+                let already_shown =
+                    self.poll.as_ref().map(|p| p.finished && p.title == poll.title).unwrap_or(false);
+
+                if poll.finished && !already_shown {
+                    let text = match poll.winner() {
+                        Some(i) => format!(
+                            "{}\n{} wins with {}%",
+                            poll.title,
+                            poll.choices[i].title,
+                            poll.percent(&poll.choices[i])
+                        ),
+                        None => format!("{}\nNobody voted", poll.title),
+                    };
+                    let animation =
+                        CharAnim::new(&text, self.inner_win.size(), self.effects.get(EffectEvent::PollResult));
+                    self.queue.push_back((animation, None));
+                    self.poll_until = Some(Instant::now() + self.poll_result);
+                } else if !poll.finished {
+                    self.poll_until = None;
+                }
+                self.poll = Some(poll.clone());
+                self.dirty = true;
+            }
+            DisplayMessage::TodoUpdate(src) => {
+                self.todo = Some(Todo::parse(src));
+                self.dirty = true;
+            }
+            DisplayMessage::TodoUnavailable(_) => {
+                self.todo = None;
                 self.dirty = true;
             }
             DisplayMessage::Chat(_)
             | DisplayMessage::Quote(..)
             | DisplayMessage::ChatEvent(_)
             | DisplayMessage::ClearChat
+            | DisplayMessage::Credits(_)
+            | DisplayMessage::Layout(_)
+            | DisplayMessage::Speak(_)
+            | DisplayMessage::Redemption(_)
+            | DisplayMessage::Raid(..)
+            | DisplayMessage::GiftBomb(..)
+            | DisplayMessage::GiftBombRecipient { .. }
             | DisplayMessage::Sub(_, _) => return Ok(()),
         };
 
         Ok(())
     }
 
+    pub fn move_win(&mut self, pos: Pos) -> Result<()> {
+        self.window.move_win(pos)?;
+        self.inner_win.move_win(Pos::new(pos.x + 1, pos.y + 1))?;
+        self.dirty = true;
+        Ok(())
+    }
+
     pub fn resize(&mut self, size: Size) -> Result<()> {
         // Cancel any ongoing animations
         // before resizing the window
@@ -143,7 +207,82 @@ impl EventDisplay {
         Ok(())
     }
 
+    fn draw_poll(&self, poll: &Poll) -> Result<()> {
+        let width = self.inner_win.size().width as usize;
+        let winner = poll.winner().filter(|_| poll.finished);
+        let label_width = poll.choices.iter().map(|c| c.title.chars().count()).max().unwrap_or(0).min(width / 3);
+        // "nn. " before the label, " 100% (n)" after the bar
+        let bar_width = width.saturating_sub(label_width + 4 + 16);
+
+        let rows = poll
+            .choices
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| {
+                let percent = poll.percent(choice);
+                let label = choice.title.chars().take(label_width).collect::<String>();
+                let bar = "█".repeat(bar_width * percent / 100);
+                let label = format!("{:>2}. {:<width$} ", i + 1, label, width = label_width);
+                let count = format!(" {:>3}% ({})", percent, choice.votes);
+                (label, bar, count)
+            })
+            .collect::<Vec<_>>();
+        let title = match poll.finished {
+            true => format!("{} (final)", poll.title),
+            false => poll.title.clone(),
+        };
+
+        let mut lines = Lines::new(width);
+        lines.style(Attribute::Bold);
+        lines.push_str(&title, true);
+        lines.reset_style();
+        lines.force_new_line();
+
+        for (i, (label, bar, count)) in rows.iter().enumerate() {
+            lines.reset_color();
+            lines.push_str(label, true);
+            let color = match winner {
+                Some(w) if w == i => Color::Green,
+                Some(_) => super::GREY,
+                None => Color::Magenta,
+            };
+            if let Ok(col) = Colors::init_fg(color) {
+                lines.color(col);
+            }
+            lines.push_str(bar, true);
+            lines.reset_color();
+            lines.push_str(count, true);
+            lines.force_new_line();
+        }
+
+        let offset = lines.len();
+        super::render_lines(lines, &self.inner_win, offset)
+    }
+
+    fn draw_todo(&self, todo: &Todo) -> Result<()> {
+        let (done, total) = todo.progress();
+        let mut lines = Lines::new(self.inner_win.size().width as usize);
+        lines.style(Attribute::Bold);
+        lines.push_str(&format!("Todo {}/{}", done, total), true);
+        lines.reset_style();
+        lines.force_new_line();
+        if let Some((_, next)) = todo.open_items().next() {
+            lines.push_str(&format!("Next: {}", next), true);
+            lines.force_new_line();
+        }
+
+        let offset = lines.len();
+        super::render_lines(lines, &self.inner_win, offset)
+    }
+
     pub fn update(&mut self) -> Result<()> {
+        // Take down the final poll result once it has been up long enough
+        if self.poll_until.map(|until| until <= Instant::now()).unwrap_or(false) {
+            self.poll = None;
+            self.poll_until = None;
+            self.dirty = true;
+        }
+
         if !self.dirty && !self.wants_update() {
             return Ok(());
         }
@@ -152,12 +291,18 @@ impl EventDisplay {
         self.window.erase()?;
         self.inner_win.erase()?;
 
-        if !self.wants_update() {
-            self.show_todo()?;
-        } else {
+        if self.wants_update() {
             self.next_frame()?;
         }
 
+        if self.current.is_none() {
+            match (&self.poll, &self.todo) {
+                (Some(poll), _) => self.draw_poll(poll)?,
+                (None, Some(todo)) if self.show_todo => self.draw_todo(todo)?,
+                _ => {}
+            }
+        }
+
         self.window.draw_box();
         let blue: i16 = Color::Blue.into();
         let blue = Colors::get_color_pair(blue as u32);
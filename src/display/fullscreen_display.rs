@@ -1,54 +1,172 @@
 use std::collections::VecDeque;
 use std::fmt::Write;
+use std::time::{Duration, Instant};
 
-use anathema::{Pos, Size, Sub, Window};
+use anathema::{Color, Colors, Pos, Size, Sub, Window};
 use anyhow::Result;
 use rodio::OutputStreamHandle;
 
-use super::animation::{get_anim_src, Animation, CharAnim, FrameAnim};
-use super::models::{DisplayMessage, Subscription};
+use super::animation::{CharAnim, FrameAnim, TextEffect};
+use super::credits::CreditsRoll;
+use super::effects::{EffectEvent, TextEffects};
+use super::models::{DisplayMessage, GiftBomb, Raid, SubType, Subscription, Tier};
+use super::particles::{Celebration, Palette, Particles};
+use super::registry::{AnimEvent, AnimationRegistry};
 use crate::audio::SoundPlayer;
+use crate::stats::Stats;
+
+// Gifting at least this many subs at once is a gift bomb
+const GIFT_BOMB: usize = 5;
+// Raids with fewer viewers get confetti instead of fireworks
+const BIG_RAID: usize = 10;
+// Time between each step of the gift counter
+const COUNTER_STEP: Duration = Duration::from_millis(150);
+// Extra time on screen per gifted sub, and the most a gift bomb can add
+const GIFT_TIME: Duration = Duration::from_millis(200);
+const MAX_GIFT_TIME: Duration = Duration::from_secs(15);
+
+// -----------------------------------------------------------------------------
+//     - Gift counter -
+//     Counts up towards the number of recipients received so far.
+// -----------------------------------------------------------------------------
+struct GiftCounter {
+    gifter: String,
+    count: usize,
+    recipients: Vec<String>,
+    shown: usize,
+    last_step: Option<Instant>,
+}
+
+impl GiftCounter {
+    fn new(gifter: String, count: usize) -> Self {
+        Self { gifter, count, recipients: Vec::new(), shown: 0, last_step: None }
+    }
+
+    fn draw<T>(&mut self, window: &mut Window<T>) -> Result<()> {
+        let now = Instant::now();
+        let last_step = *self.last_step.get_or_insert(now);
+        if self.shown < self.recipients.len() && now - last_step >= COUNTER_STEP {
+            self.shown += 1;
+            self.last_step = Some(now);
+        }
+
+        let width = window.size().width;
+        let counter = format!("{} / {}", self.shown, self.count);
+        let color_id: i16 = match self.shown == self.count {
+            true => Color::Yellow.into(),
+            false => Color::Cyan.into(),
+        };
+        window.set_color(Colors::get_color_pair(color_id as u32))?;
+        window.print_at(Pos::new((width / 2 - counter.len() as i32 / 2).max(0), 1), &counter)?;
+        window.set_color(Colors::get_color_pair(0))?;
+
+        if let Some(recipient) = self.shown.checked_sub(1).and_then(|i| self.recipients.get(i)) {
+            let x = (width / 2 - recipient.chars().count() as i32 / 2).max(0);
+            window.print_at(Pos::new(x, 2), recipient)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct Alert {
+    frame: Option<FrameAnim>,
+    text: CharAnim,
+    particles: Option<Particles>,
+    counter: Option<GiftCounter>,
+    sound_path: String,
+}
 
 pub struct FullscreenDisplay {
-    queue: VecDeque<(String, FrameAnim, CharAnim, String)>,
-    current: Option<(FrameAnim, CharAnim)>,
+    queue: VecDeque<Alert>,
+    current: Option<Alert>,
+    credits: Option<CreditsRoll>,
+    animations: AnimationRegistry,
+    effects: TextEffects,
     sound_player: Option<SoundPlayer>,
     output_handle: OutputStreamHandle,
     window: Window<Sub>,
 }
 
 impl FullscreenDisplay {
-    pub fn new(window: Window<Sub>, output_handle: OutputStreamHandle) -> Self {
-        Self { queue: VecDeque::with_capacity(100), current: None, sound_player: None, output_handle, window }
+    pub fn new(
+        window: Window<Sub>,
+        output_handle: OutputStreamHandle,
+        animations: AnimationRegistry,
+        effects: TextEffects,
+    ) -> Self {
+        Self {
+            queue: VecDeque::with_capacity(100),
+            current: None,
+            credits: None,
+            animations,
+            effects,
+            sound_player: None,
+            output_handle,
+            window,
+        }
     }
 
     pub fn wants_update(&self) -> bool {
-        !self.queue.is_empty() || self.current.is_some()
+        !self.queue.is_empty() || self.current.is_some() || self.credits.is_some()
+    }
+
+    /// Start rolling the credits from the beginning.
+    pub fn roll_credits(&mut self, stats: &Stats) {
+        self.credits = Some(CreditsRoll::new(stats, self.window.size()));
     }
 
     fn next_frame(&mut self) -> Result<()> {
+        // The credits take over the entire screen until they are done,
+        // holding back any queued subs.
+        if let Some(credits) = &mut self.credits {
+            credits.draw(&mut self.window)?;
+            if credits.is_done {
+                self.credits.take();
+            }
+            return Ok(());
+        }
+
         match &mut self.current {
-            Some((frame, text)) => {
-                text.draw(&mut self.window)?;
+            Some(alert) => {
+                // Particles go behind everything else
+                let particles_done = match &mut alert.particles {
+                    Some(particles) => {
+                        particles.draw(&mut self.window)?;
+                        particles.is_done
+                    }
+                    None => true,
+                };
+
+                alert.text.draw(&mut self.window)?;
+
+                if let Some(counter) = &mut alert.counter {
+                    counter.draw(&mut self.window)?;
+                }
 
                 // ... Then the frame anim
-                let lines = frame.update();
-                // Position the animation at the bottom of the window
-                let y = self.window.size().height - lines.len() as i32;
-                self.window.move_cursor(Pos::new(0, y - 1))?;
-                super::render_lines(lines, &self.window, 0)?;
+                let frame_done = match &mut alert.frame {
+                    Some(frame) => {
+                        let lines = frame.update();
+                        // Position the animation at the bottom of the window
+                        let y = self.window.size().height - lines.len() as i32;
+                        self.window.move_cursor(Pos::new(0, y - 1))?;
+                        super::render_lines(lines, &self.window, 0)?;
+                        frame.is_done
+                    }
+                    None => true,
+                };
 
-                if frame.is_done && text.is_done {
+                if frame_done && alert.text.is_done && particles_done {
                     self.current.take();
                 }
             }
             None => match self.queue.pop_front() {
-                Some(next_anim) => {
-                    let (message, anim, text, sound_path) = next_anim;
-                    self.current = Some((anim, text));
-                    let mut player = SoundPlayer::new(sound_path, self.output_handle.clone());
+                Some(alert) => {
+                    let mut player = SoundPlayer::new(&alert.sound_path, self.output_handle.clone());
                     player.play(1.0);
                     self.sound_player = Some(player);
+                    self.current = Some(alert);
                 }
                 None => {}
             },
@@ -68,52 +186,203 @@ impl FullscreenDisplay {
         // Cancel any ongoing animations
         // before resizing the window
         let _ = self.current.take();
+        let _ = self.credits.take();
 
         self.window.resize(size)?;
         Ok(())
     }
 
     pub fn handle(&mut self, msg: &DisplayMessage) -> Result<()> {
-        let (sub, sound_path) = match msg {
-            DisplayMessage::Sub(sub, sound_path) => (sub, sound_path),
+        match msg {
+            DisplayMessage::Sub(sub, sound_path) => self.queue_sub(sub, sound_path)?,
+            DisplayMessage::GiftBomb(bomb, sound_path) => self.queue_gift_bomb(bomb, sound_path),
+            DisplayMessage::GiftBombRecipient { gifter, recipient } => self.gift_recipient(gifter, recipient),
+            DisplayMessage::Raid(raid, sound_path) => self.queue_raid(raid, sound_path),
+            DisplayMessage::Credits(stats) => self.roll_credits(stats),
             DisplayMessage::ChannelPoints(_)
+            | DisplayMessage::Bits(..)
             | DisplayMessage::Quote(..)
             | DisplayMessage::Chat(_)
             | DisplayMessage::Follow(..)
             | DisplayMessage::TodoUpdate(_)
+            | DisplayMessage::TodoUnavailable(_)
             | DisplayMessage::ChatEvent(_)
-            | DisplayMessage::ClearChat => return Ok(()),
-        };
+            | DisplayMessage::Layout(_)
+            | DisplayMessage::Poll(_)
+            | DisplayMessage::Speak(_)
+            | DisplayMessage::Redemption(_)
+            | DisplayMessage::ClearChat => {}
+        }
 
-        let width = self.window.size().width;
-        let anim_src = get_anim_src(sub.tier);
-        let mut animation = FrameAnim::new(anim_src, width as usize);
+        Ok(())
+    }
 
-        let height = self.window.size().height;
-        let text_anim_height = height - animation.height as i32;
-        let message = sub_to_message(&sub, text_anim_height as usize)?;
-        let mut char_anim = CharAnim::new(&message, Size::new(width, text_anim_height), Animation::Scatter);
-        if animation.ttl > char_anim.ttl {
-            char_anim.ttl = animation.ttl;
-        } else {
-            animation.ttl = char_anim.ttl;
+    fn frame_anim(&self, event: AnimEvent, tier: Tier, months: Option<usize>, name: &str) -> Option<FrameAnim> {
+        let width = self.window.size().width as usize;
+        let params = [("name", name)];
+        match self.animations.pick(event, tier, months) {
+            Some(anim_src) => match FrameAnim::new(anim_src, width, &params) {
+                Ok(anim) => Some(anim),
+                Err(e) => {
+                    log::error!("Failed to load {}: {}", anim_src.display(), e);
+                    None
+                }
+            },
+            None => {
+                log::warn!("No animation for {:?} ({})", event, tier.name());
+                None
+            }
         }
-        self.queue.push_back((message, animation, char_anim, sound_path.clone()));
+    }
 
+    // Height left for the text above the frame animation
+    fn text_height(&self, frame: &Option<FrameAnim>) -> i32 {
+        self.window.size().height - frame.as_ref().map(|f| f.height).unwrap_or(0) as i32
+    }
+
+    fn queue_alert(
+        &mut self,
+        frame: Option<FrameAnim>,
+        message: &str,
+        effect: TextEffect,
+        celebration: Option<(Celebration, Palette)>,
+        sound_path: &str,
+    ) {
+        let alert = self.alert(frame, message, effect, celebration, Duration::ZERO, sound_path);
+        self.queue.push_back(alert);
+    }
+
+    fn alert(
+        &self,
+        mut frame: Option<FrameAnim>,
+        message: &str,
+        effect: TextEffect,
+        celebration: Option<(Celebration, Palette)>,
+        extra_time: Duration,
+        sound_path: &str,
+    ) -> Alert {
+        let size = Size::new(self.window.size().width, self.text_height(&frame));
+        let mut text = CharAnim::new(message, size, effect);
+        text.ttl += extra_time;
+        match &mut frame {
+            Some(frame) if frame.ttl > text.ttl => text.ttl = frame.ttl,
+            Some(frame) => frame.ttl = text.ttl,
+            None => {}
+        }
+
+        let particles = celebration
+            .map(|(celebration, palette)| Particles::new(celebration, palette, self.window.size(), text.ttl));
+
+        Alert { frame, text, particles, counter: None, sound_path: sound_path.to_string() }
+    }
+
+    fn queue_sub(&mut self, sub: &Subscription, sound_path: &str) -> Result<()> {
+        let name = sub.display_name.as_deref().unwrap_or("[Anonymous]");
+        let frame = self.frame_anim(AnimEvent::from_sub(sub), sub.tier, sub.cumulative_months, name);
+        let message = sub_to_message(&sub, self.text_height(&frame) as usize)?;
+        let effect = self.effects.get(EffectEvent::Sub);
+        self.queue_alert(frame, &message, effect, sub_celebration(sub), sound_path);
         Ok(())
     }
+
+    fn queue_gift_bomb(&mut self, bomb: &GiftBomb, sound_path: &str) {
+        let frame = self.frame_anim(AnimEvent::Gift, bomb.tier, None, &bomb.gifter);
+        let mut message = format!("{} is gifting {} subs!", bomb.gifter, bomb.count);
+        if let Some(total @ 2..) = bomb.lifetime_total {
+            message.push_str(&format!("\nThat's {} gifted subs in total!", total));
+        }
+        let effect = self.effects.get(EffectEvent::Sub);
+        // Bigger bombs stay on screen for longer
+        let extra_time = (GIFT_TIME * bomb.count as u32).min(MAX_GIFT_TIME);
+
+        let mut alert = self.alert(frame, &message, effect, gift_bomb_celebration(bomb.count), extra_time, sound_path);
+        let mut counter = GiftCounter::new(bomb.gifter.clone(), bomb.count);
+        counter.recipients = bomb.recipients.clone();
+        alert.counter = Some(counter);
+        self.queue.push_back(alert);
+    }
+
+    // Recipients can arrive before the bomb is on screen,
+    // so look in the queue as well.
+    fn gift_recipient(&mut self, gifter: &str, recipient: &str) {
+        let counter = self
+            .current
+            .iter_mut()
+            .chain(self.queue.iter_mut())
+            .filter_map(|alert| alert.counter.as_mut())
+            .find(|counter| counter.gifter == gifter && counter.recipients.len() < counter.count);
+
+        match counter {
+            Some(counter) => counter.recipients.push(recipient.to_string()),
+            None => log::warn!("{} gifted a sub to {} but there is no gift bomb", gifter, recipient),
+        }
+    }
+
+    fn queue_raid(&mut self, raid: &Raid, sound_path: &str) {
+        let frame = self.frame_anim(AnimEvent::Raid, Tier::Unknown, None, &raid.name);
+        let message = format!("{} is raiding with {} viewers!", raid.name, raid.viewers);
+        let effect = self.effects.get(EffectEvent::Raid);
+        let celebration = match raid.viewers >= BIG_RAID {
+            true => (Celebration::Fireworks, Palette::Rainbow),
+            false => (Celebration::Confetti, Palette::Rainbow),
+        };
+        self.queue_alert(frame, &message, effect, Some(celebration), sound_path);
+    }
+}
+
+// The more subs, the bigger the party
+fn gift_bomb_celebration(count: usize) -> Option<(Celebration, Palette)> {
+    match count {
+        0..=4 => None,
+        5..=19 => Some((Celebration::Confetti, Palette::Rainbow)),
+        20..=49 => Some((Celebration::Fireworks, Palette::Rainbow)),
+        _ => Some((Celebration::Fireworks, Palette::Gold)),
+    }
+}
+
+// Only the big moments get particles
+fn sub_celebration(sub: &Subscription) -> Option<(Celebration, Palette)> {
+    if let Tier::Three = sub.tier {
+        return Some((Celebration::Fireworks, Palette::Gold));
+    }
+
+    if sub.gift && sub.recipients.len() >= GIFT_BOMB {
+        return gift_bomb_celebration(sub.recipients.len());
+    }
+
+    match sub.cumulative_months {
+        Some(12..) => Some((Celebration::Snow('★'), Palette::Ice)),
+        Some(months) if milestone(months).is_some() => Some((Celebration::Confetti, Palette::Ice)),
+        _ => None,
+    }
+}
+
+fn milestone(months: usize) -> Option<&'static str> {
+    match months {
+        6 => Some("~ Half a year! ~"),
+        12 => Some("~ One whole year! ~"),
+        24 => Some("~ Two years!! ~"),
+        _ => None,
+    }
 }
 
 fn sub_to_message(sub: &Subscription, max_lines: usize) -> Result<String> {
     let mut s = String::new();
+    let name = sub.display_name.as_deref().unwrap_or("[Anonymous]");
+
+    let is_gift = sub.gift || matches!(sub.sub_type, SubType::Gift | SubType::AnonGift);
+    let is_anon = matches!(sub.sub_type, SubType::AnonGift) || sub.display_name.is_none();
+
+    if is_gift {
+        match is_anon {
+            true => write!(&mut s, "An anonymous gifter gifted ")?,
+            false => write!(&mut s, "{} gifted ", name)?,
+        }
 
-    // Gift
-    if sub.gift {
-        write!(&mut s, "{} gifted ", sub.display_name.as_deref().unwrap_or("[Anonymous]"))?;
         if sub.recipients.len() == 1 {
-            write!(&mut s, "a sub to {}!", sub.recipients.first().unwrap())?;
+            write!(&mut s, "a {} sub to {}!", sub.tier.name(), sub.recipients.first().unwrap())?;
         } else {
-            write!(&mut s, "{} subs to \n", sub.recipients.len())?;
+            write!(&mut s, "{} {} subs to \n", sub.recipients.len(), sub.tier.name())?;
             sub.recipients.iter().take(max_lines.saturating_sub(5)).for_each(|r| {
                 if let Err(e) = write!(&mut s, "{}\n", r) {
                     log::error!("failed to write to string: {}", e);
@@ -124,20 +393,91 @@ fn sub_to_message(sub: &Subscription, max_lines: usize) -> Result<String> {
                 write!(&mut s, "... And many more!!!!")?;
             }
         }
-    // Not gift
-    } else {
-        write!(&mut s, "{} subscribed", sub.display_name.as_deref().unwrap_or("[Anonymous]"))?;
+    } else if let Some(months @ 2..) = sub.multi_month {
+        write!(&mut s, "{} subscribed for {} months in advance!", name, months)?;
+    } else if let SubType::Resub = sub.sub_type {
+        match sub.tier {
+            Tier::Prime => write!(&mut s, "{} resubscribed with Prime", name)?,
+            _ => write!(&mut s, "{} resubscribed at {}", name, sub.tier.name())?,
+        }
         match sub.cumulative_months {
-            Some(months @ 2..) => {
-                write!(&mut s, " for {} months!", months)?;
-            }
-            _ => {}
+            Some(months @ 2..) => write!(&mut s, " for {} months!", months)?,
+            _ => write!(&mut s, "!")?,
         }
+    } else {
+        match sub.tier {
+            Tier::Prime => write!(&mut s, "{} subscribed with Prime!", name)?,
+            Tier::Unknown => write!(&mut s, "{} subscribed!", name)?,
+            _ => write!(&mut s, "{} subscribed at {}!", name, sub.tier.name())?,
+        }
+    }
 
+    // Gifts are about the recipients, not the gifter's months
+    if !is_gift {
         if let Some(streak @ 2..) = sub.streak {
             write!(&mut s, "\nThey have subscribed for {} months in a row now!", streak)?;
         }
+
+        if let Some(milestone) = sub.cumulative_months.and_then(milestone) {
+            write!(&mut s, "\n\n{}", milestone)?;
+        }
+
+        if !sub.message.is_empty() {
+            write!(&mut s, "\n\n\"{}\"", sub.message.trim())?;
+        }
     }
 
     Ok(s)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resub(months: usize, message: &str) -> Subscription {
+        Subscription {
+            gift: false,
+            display_name: Some("person".to_string()),
+            recipients: vec![],
+            tier: Tier::Prime,
+            message: message.to_string(),
+            sub_type: SubType::Resub,
+            cumulative_months: Some(months),
+            streak: None,
+            multi_month: None,
+        }
+    }
+
+    #[test]
+    fn resub_with_message() {
+        let message = sub_to_message(&resub(3, "hello"), 20).unwrap();
+        assert_eq!(message, "person resubscribed with Prime for 3 months!\n\n\"hello\"");
+    }
+
+    #[test]
+    fn multi_month() {
+        let mut sub = resub(3, "");
+        sub.tier = Tier::One;
+        sub.multi_month = Some(6);
+        let message = sub_to_message(&sub, 20).unwrap();
+        assert_eq!(message, "person subscribed for 6 months in advance!");
+    }
+
+    #[test]
+    fn milestone_callout() {
+        let message = sub_to_message(&resub(12, ""), 20).unwrap();
+        assert!(message.ends_with("~ One whole year! ~"));
+    }
+
+    #[test]
+    fn anon_gift() {
+        let mut sub = resub(1, "ignored");
+        sub.gift = true;
+        sub.display_name = None;
+        sub.tier = Tier::One;
+        sub.sub_type = SubType::AnonGift;
+        sub.recipients = vec!["someone".to_string()];
+        let message = sub_to_message(&sub, 20).unwrap();
+        assert_eq!(message, "An anonymous gifter gifted a Tier 1 sub to someone!");
+    }
+}
@@ -0,0 +1,264 @@
+use anathema::{Pos, Size};
+use serde::Deserialize;
+
+const EVENT_HEIGHT: i32 = 9;
+
+/// Everything that can be placed in a layout
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pane {
+    Chat,
+    Events,
+    Todo,
+    Redemptions,
+}
+
+/// Size of a row or column
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "RawExtent")]
+pub enum Extent {
+    Fixed(i32),
+    Percent(i32),
+    /// Share whatever is left with the other `Fill`s
+    #[default]
+    Fill,
+}
+
+// Sizes are written as `9`, `"30%"` or `"fill"`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawExtent {
+    Fixed(i32),
+    Text(String),
+}
+
+impl TryFrom<RawExtent> for Extent {
+    type Error = String;
+
+    fn try_from(raw: RawExtent) -> Result<Self, Self::Error> {
+        match raw {
+            RawExtent::Fixed(n) if n >= 0 => Ok(Extent::Fixed(n)),
+            RawExtent::Text(s) if s == "fill" => Ok(Extent::Fill),
+            RawExtent::Text(s) => match s.strip_suffix('%').and_then(|p| p.parse::<i32>().ok()) {
+                Some(p @ 0..=100) => Ok(Extent::Percent(p)),
+                _ => Err(format!("invalid size \"{}\", expected a number, a percentage or \"fill\"", s)),
+            },
+            RawExtent::Fixed(n) => Err(format!("invalid size {}", n)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Node {
+    Rows(Vec<Child>),
+    Columns(Vec<Child>),
+    Pane(Pane),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Child {
+    #[serde(default)]
+    pub size: Extent,
+    #[serde(flatten)]
+    pub node: Node,
+}
+
+// -----------------------------------------------------------------------------
+//     - Layout -
+//     A tree of rows and columns with panes as leaves, e.g:
+//     { "columns": [
+//         { "size": "70%", "pane": "chat" },
+//         { "rows": [{ "size": 9, "pane": "events" }, { "pane": "chat" }] }
+//     ] }
+//     Panes that aren't in the layout are hidden. Without a todo pane
+//     the event panel shows the todo progress when it's idle.
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
+pub struct Layout(pub Node);
+
+/// Events and the todo list on top, chat below
+impl Default for Layout {
+    fn default() -> Self {
+        let top = vec![
+            Child { size: Extent::Fill, node: Node::Pane(Pane::Events) },
+            Child { size: Extent::Percent(40), node: Node::Pane(Pane::Todo) },
+        ];
+        Layout(Node::Rows(vec![
+            Child { size: Extent::Fixed(EVENT_HEIGHT), node: Node::Columns(top) },
+            Child { size: Extent::Fill, node: Node::Pane(Pane::Chat) },
+        ]))
+    }
+}
+
+impl Layout {
+    /// Like the default, with unfulfilled redemptions instead of the todo list
+    pub fn redemptions() -> Self {
+        let top = vec![
+            Child { size: Extent::Fill, node: Node::Pane(Pane::Events) },
+            Child { size: Extent::Percent(40), node: Node::Pane(Pane::Redemptions) },
+        ];
+        Layout(Node::Rows(vec![
+            Child { size: Extent::Fixed(EVENT_HEIGHT), node: Node::Columns(top) },
+            Child { size: Extent::Fill, node: Node::Pane(Pane::Chat) },
+        ]))
+    }
+
+    /// Position and size of every visible pane.
+    /// Panes that don't fit are left out.
+    pub fn place(&self, total: Size) -> Vec<(Pane, Pos, Size)> {
+        let mut panes = vec![];
+        place_node(&self.0, Pos::new(0, 0), total, &mut panes);
+        panes
+    }
+
+    pub fn get(&self, pane: Pane, total: Size) -> Option<(Pos, Size)> {
+        self.place(total).into_iter().find(|(p, ..)| *p == pane).map(|(_, pos, size)| (pos, size))
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Named layouts -
+// -----------------------------------------------------------------------------
+pub struct Layouts {
+    layouts: Vec<(String, Layout)>,
+    current: usize,
+}
+
+impl Layouts {
+    pub fn new(layouts: impl IntoIterator<Item = (String, Layout)>, current: &str) -> Self {
+        let layouts = layouts.into_iter().collect::<Vec<_>>();
+        let current = layouts.iter().position(|(name, _)| name == current).unwrap_or(0);
+        Self { layouts, current }
+    }
+
+    pub fn current(&self) -> &Layout {
+        &self.layouts[self.current].1
+    }
+
+    pub fn name(&self) -> &str {
+        &self.layouts[self.current].0
+    }
+
+    /// Switch to the next layout, wrapping around
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.layouts.len();
+    }
+
+    /// Switch to a layout by name.
+    /// Returns false if there is no such layout.
+    pub fn select(&mut self, name: &str) -> bool {
+        match self.layouts.iter().position(|(n, _)| n == name) {
+            Some(index) => {
+                self.current = index;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn place_node(node: &Node, pos: Pos, size: Size, panes: &mut Vec<(Pane, Pos, Size)>) {
+    match node {
+        Node::Pane(pane) => {
+            if size.width > 0 && size.height > 0 {
+                panes.push((*pane, pos, size));
+            }
+        }
+        Node::Rows(children) => {
+            let mut y = pos.y;
+            for (child, height) in children.iter().zip(split(children, size.height)) {
+                place_node(&child.node, Pos::new(pos.x, y), Size::new(size.width, height), panes);
+                y += height;
+            }
+        }
+        Node::Columns(children) => {
+            let mut x = pos.x;
+            for (child, width) in children.iter().zip(split(children, size.width)) {
+                place_node(&child.node, Pos::new(x, pos.y), Size::new(width, size.height), panes);
+                x += width;
+            }
+        }
+    }
+}
+
+// Fixed and percentage sizes are handed out first, in order,
+// until there is no room left. `Fill` shares the rest.
+// If there is a `Fill`, a child that doesn't fit is hidden and the
+// `Fill` gets its room instead, e.g. chat takes the whole of a short screen.
+fn split(children: &[Child], total: i32) -> Vec<i32> {
+    let fills = children.iter().filter(|c| c.size == Extent::Fill).count() as i32;
+    let mut left = total;
+    let mut sizes = children
+        .iter()
+        .map(|child| {
+            let wanted = match child.size {
+                Extent::Fixed(n) => n,
+                Extent::Percent(p) => total * p / 100,
+                Extent::Fill => return 0,
+            };
+            if wanted > left && fills > 0 {
+                return 0;
+            }
+            let size = wanted.min(left);
+            left -= size;
+            size
+        })
+        .collect::<Vec<_>>();
+
+    let mut fill_index = 0;
+    for (size, child) in sizes.iter_mut().zip(children) {
+        if child.size == Extent::Fill {
+            // The first fills get the remainder
+            *size = left / fills + (fill_index < left % fills) as i32;
+            fill_index += 1;
+        }
+    }
+
+    sizes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rects(layout: &Layout, width: i32, height: i32) -> Vec<(Pane, i32, i32, i32, i32)> {
+        layout
+            .place(Size::new(width, height))
+            .into_iter()
+            .map(|(pane, pos, size)| (pane, pos.x, pos.y, size.width, size.height))
+            .collect()
+    }
+
+    #[test]
+    fn default_layout() {
+        let layout = Layout::default();
+        let panes = rects(&layout, 80, 30);
+        let expected = vec![(Pane::Events, 0, 0, 48, 9), (Pane::Todo, 48, 0, 32, 9), (Pane::Chat, 0, 9, 80, 21)];
+        assert_eq!(panes, expected);
+    }
+
+    #[test]
+    fn not_enough_room() {
+        let layout = Layout::default();
+        let panes = rects(&layout, 80, 5);
+        assert_eq!(panes, vec![(Pane::Chat, 0, 0, 80, 5)]);
+    }
+
+    #[test]
+    fn columns_and_percent() {
+        let json = r#"{ "columns": [
+            { "size": "25%", "rows": [{ "size": 9, "pane": "events" }] },
+            { "pane": "chat" }
+        ] }"#;
+        let layout: Layout = serde_json::from_str(json).unwrap();
+        let panes = rects(&layout, 100, 30);
+        assert_eq!(panes, vec![(Pane::Events, 0, 0, 25, 9), (Pane::Chat, 25, 0, 75, 30)]);
+    }
+
+    #[test]
+    fn invalid_size() {
+        let json = r#"{ "rows": [{ "size": "120%", "pane": "chat" }] }"#;
+        assert!(serde_json::from_str::<Layout>(json).is_err());
+    }
+}
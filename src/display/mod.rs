@@ -1,5 +1,6 @@
+use std::path::Path;
 use std::sync::mpsc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use unicode_width::UnicodeWidthStr;
 use anathema::{Color, Colors, Cursor, Input, Instruction, Lines, Pos, Size, Sub, Window};
@@ -7,23 +8,48 @@ use anyhow::Result;
 use rand::prelude::*;
 use rodio::OutputStream;
 
+use crate::audio::Speaker;
+use crate::config::Config;
+use crate::control::Control;
+use crate::redemptions::{DecisionTx, RedemptionStore};
+
+pub mod anim_file;
 mod animation;
 mod chat_display;
+mod credits;
+mod easing;
+mod effects;
 mod event_display;
 mod fullscreen_display;
+pub mod layout;
 pub mod models;
+mod particles;
+mod redemption_display;
+mod registry;
+mod todo_display;
+mod scheduler;
 
+use animation::{Animation, CharAnim, FrameAnim};
 use chat_display::ChatDisplay;
+use credits::CreditsRoll;
+use effects::TextEffects;
 use event_display::EventDisplay;
 use fullscreen_display::FullscreenDisplay;
+use layout::{Layout, Layouts, Pane};
+use models::DisplayMessage;
+use registry::AnimationRegistry;
+use scheduler::{Panel, Scheduler};
+use redemption_display::RedemptionDisplay;
+use todo_display::TodoDisplay;
 
 pub type DisplayEventRx = mpsc::Receiver<models::DisplayMessage>;
 pub type DisplayEventTx = mpsc::Sender<models::DisplayMessage>;
 
-const EVENT_HEIGHT: i32 = 9;
+pub const ANIMATION_DIR: &str = "animations";
 const NAP_TIME: u64 = 30;
 
 pub const GREY: Color = Color::Id(80);
+pub const DARK_GREY: Color = Color::Id(81);
 
 pub fn channel() -> (DisplayEventTx, DisplayEventRx) {
     mpsc::channel()
@@ -41,6 +67,14 @@ fn setup_colors() {
     }
 
     Colors::init_color(GREY.into(), 400, 400, 400).unwrap();
+    Colors::init_color(DARK_GREY.into(), 200, 200, 200).unwrap();
+
+    // Greys used when fading out text
+    for c in [GREY, DARK_GREY] {
+        if let Err(e) = Colors::init_fg(c) {
+            log::error!("Failed to init grey: {}", e);
+        }
+    }
 }
 
 pub fn random_color() -> Color {
@@ -51,25 +85,50 @@ pub fn random_color() -> Color {
     *COLORS.choose(&mut rng).expect("this really shouldn't fail")
 }
 
-// Get the sizes for the event view and the chat view.
-// If there isn't enough space for the events, set the size to
-// zero and don't draw it.
-pub fn sizes(total: Size) -> (Size, Size) {
-    let event_size = {
-        match total.height > EVENT_HEIGHT {
-            true => Size::new(total.width, EVENT_HEIGHT),
-            false => Size::new(total.width, 0),
+// Move and resize the panes to fit the layout,
+// and return the ones that are visible.
+// Hidden panes keep their old windows but are never drawn.
+fn apply_layout(
+    layout: &Layout,
+    total: Size,
+    chat: &mut ChatDisplay,
+    event_disp: &mut EventDisplay,
+    todo: &mut TodoDisplay,
+    redemptions: &mut RedemptionDisplay,
+) -> Result<Vec<Pane>> {
+    let placed = layout.place(total);
+    event_disp.show_todo(!placed.iter().any(|(pane, ..)| *pane == Pane::Todo));
+    for (pane, pos, size) in &placed {
+        match pane {
+            Pane::Chat => {
+                chat.move_win(*pos)?;
+                chat.resize(*size)?;
+            }
+            Pane::Events => {
+                event_disp.move_win(*pos)?;
+                event_disp.resize(*size)?;
+            }
+            Pane::Todo => {
+                todo.move_win(*pos)?;
+                todo.resize(*size)?;
+            }
+            Pane::Redemptions => {
+                redemptions.move_win(*pos)?;
+                redemptions.resize(*size)?;
+            }
         }
-    };
-    let chat_size = match event_size.height {
-        EVENT_HEIGHT => Size::new(total.width, total.height - EVENT_HEIGHT),
-        _ => total,
-    };
+    }
 
-    (event_size, chat_size)
+    Ok(placed.into_iter().map(|(pane, ..)| pane).collect())
 }
 
-pub fn run(events: DisplayEventRx) -> Result<()> {
+pub fn run(events: DisplayEventRx, control: crate::EventSender, config: Config, decisions: DecisionTx) -> Result<()> {
+    let store = RedemptionStore::load(&config.redemptions_path)?;
+    // Load the animations before taking over the terminal
+    // so any problems with the manifest are easy to see.
+    let animations = AnimationRegistry::load(ANIMATION_DIR)?;
+    let effects = TextEffects::load(ANIMATION_DIR)?;
+
     let window = Window::main(true)?;
     window.no_delay(true)?;
     window.set_cursor_visibility(Cursor::Hide)?;
@@ -79,42 +138,137 @@ pub fn run(events: DisplayEventRx) -> Result<()> {
 
     let (_stream, sound_output_handle) = OutputStream::try_default()?;
 
-    let (event_size, chat_size) = sizes(window.size());
-    let event_win = window.new_window(Pos::new(0, 0), event_size)?;
-    let chat_win = window.new_window(Pos::new(0, event_size.height), chat_size)?;
+    let mut layouts = Layouts::new(config.layouts, &config.layout);
+
+    // Hidden panes get the whole screen until a layout shows them
+    let whole = (Pos::new(0, 0), window.size());
+    let (event_pos, event_size) = layouts.current().get(Pane::Events, window.size()).unwrap_or(whole);
+    let (chat_pos, chat_size) = layouts.current().get(Pane::Chat, window.size()).unwrap_or(whole);
+    let (todo_pos, todo_size) = layouts.current().get(Pane::Todo, window.size()).unwrap_or(whole);
+    let (redemption_pos, redemption_size) = layouts.current().get(Pane::Redemptions, window.size()).unwrap_or(whole);
+    let event_win = window.new_window(event_pos, event_size)?;
+    let chat_win = window.new_window(chat_pos, chat_size)?;
+    let todo_win = window.new_window(todo_pos, todo_size)?;
+    let redemptions_win = window.new_window(redemption_pos, redemption_size)?;
     let fullscreen_win = window.new_window(Pos::new(0, 0), window.size())?;
 
     let mut chat = ChatDisplay::new(chat_win);
-    let mut event_disp = EventDisplay::new(event_win, sound_output_handle.clone(), None)?;
-    let mut fullscreen = FullscreenDisplay::new(fullscreen_win, sound_output_handle);
+    let mut event_disp = EventDisplay::new(
+        event_win,
+        sound_output_handle.clone(),
+        effects.clone(),
+        Duration::from_secs(config.polls.result_secs),
+    )?;
+    let mut todo = TodoDisplay::new(todo_win)?;
+    let mut redemptions = RedemptionDisplay::new(redemptions_win, store, decisions)?;
+    let mut fullscreen = FullscreenDisplay::new(fullscreen_win, sound_output_handle.clone(), animations, effects);
+    let mut scheduler = Scheduler::new(Duration::from_millis(config.alerts.min_gap_ms));
+    let mut speaker = Speaker::new(config.tts.command, config.tts.queue_max, sound_output_handle);
+    let mut visible = layouts.current().place(window.size()).into_iter().map(|(pane, ..)| pane).collect::<Vec<_>>();
+    event_disp.show_todo(!visible.contains(&Pane::Todo));
 
     loop {
         // ---------------------------------------------------------------------
         //     - Incoming events -
         // ---------------------------------------------------------------------
         while let Ok(event) = events.try_recv() {
+            if let DisplayMessage::Layout(name) = &event {
+                match layouts.select(name) {
+                    true => {
+                        window.erase()?;
+                        window.refresh()?;
+                        let layout = layouts.current();
+                        visible = apply_layout(
+                            layout,
+                            window.size(),
+                            &mut chat,
+                            &mut event_disp,
+                            &mut todo,
+                            &mut redemptions,
+                        )?;
+                    }
+                    false => log::warn!("No layout called \"{}\"", name),
+                }
+                continue;
+            }
+
+            if let DisplayMessage::Speak(speech) = event {
+                speaker.say(speech);
+                continue;
+            }
+
             chat.handle(&event);
-            event_disp.handle(&event)?;
-            fullscreen.handle(&event)?;
+            todo.handle(&event);
+            redemptions.handle(&event);
+
+            // Alerts wait in the scheduler, everything else
+            // goes straight to the panels
+            if let Some(event) = scheduler.push(event) {
+                event_disp.handle(&event)?;
+                fullscreen.handle(&event)?;
+            }
         }
 
+        // ---------------------------------------------------------------------
+        //     - Next alert -
+        // ---------------------------------------------------------------------
+        let busy = fullscreen.wants_update() || event_disp.wants_update();
+        scheduler.hide_events(!visible.contains(&Pane::Events));
+        match scheduler.next(busy, Instant::now()) {
+            Some((Panel::Fullscreen, alert)) => fullscreen.handle(&alert)?,
+            Some((Panel::Event, alert)) => event_disp.handle(&alert)?,
+            None => {}
+        }
+
+        speaker.update();
+
         // ---------------------------------------------------------------------
         //     - Input handling -
         // ---------------------------------------------------------------------
         if let Some(key) = window.get_input() {
             chat.input(key)?;
+            if visible.contains(&Pane::Redemptions) {
+                redemptions.input(key);
+            }
 
             match key {
                 Input::Character('c') => break Ok(()),
+                Input::Character('r') => {
+                    // The transformers own the stats, so the credits come from them,
+                    // the same as the credits control command
+                    if let Err(e) = control.try_send(crate::Event::Control(Control::Credits)) {
+                        log::error!("Failed to ask for the credits: {}", e);
+                    }
+                }
+                Input::Character('s') => speaker.skip(),
+                Input::Character('l') => {
+                    layouts.next();
+                    log::info!("Layout: {}", layouts.name());
+                    window.erase()?;
+                    window.refresh()?;
+                    let layout = layouts.current();
+                    visible = apply_layout(
+                        layout,
+                        window.size(),
+                        &mut chat,
+                        &mut event_disp,
+                        &mut todo,
+                        &mut redemptions,
+                    )?;
+                }
                 Input::KeyResize => {
                     // ---------------------------------------------------------
                     //     - Resize all windows -
                     // ---------------------------------------------------------
-                    let (event_size, chat_size) = sizes(window.size());
-
-                    chat.move_win(Pos::new(0, event_size.height))?;
-                    chat.resize(chat_size)?;
-                    event_disp.resize(event_size)?;
+                    let layout = layouts.current();
+                    visible = apply_layout(
+                        layout,
+                        window.size(),
+                        &mut chat,
+                        &mut event_disp,
+                        &mut todo,
+                        &mut redemptions,
+                    )?;
                     fullscreen.resize(window.size())?;
                 }
                 _ => {}
@@ -124,23 +278,115 @@ pub fn run(events: DisplayEventRx) -> Result<()> {
         // ---------------------------------------------------------------------
         //     - Update and draw -
         // ---------------------------------------------------------------------
-        while fullscreen.wants_update() && !event_disp.wants_update() {
+        // The fullscreen display covers the other panels while it plays
+        if fullscreen.wants_update() {
             fullscreen.update()?;
-            window.nap(Duration::from_millis(NAP_TIME))?;
 
             // If `fullscreen` is done drawing,
             // mark the chat and event display as dirty so they redraw.
             if !fullscreen.wants_update() {
                 chat.touch();
                 event_disp.touch();
+                todo.touch();
+                redemptions.touch();
+            }
+        } else {
+            if visible.contains(&Pane::Chat) {
+                chat.update(&mut colors)?;
+            }
+            if visible.contains(&Pane::Events) {
+                event_disp.update()?;
+            }
+            // The todo list stays up while the event panel plays alerts
+            if visible.contains(&Pane::Todo) {
+                todo.update()?;
+            }
+            if visible.contains(&Pane::Redemptions) {
+                redemptions.update()?;
             }
         }
 
-        chat.update(&mut colors)?;
-        event_disp.update()?;
+        window.nap(Duration::from_millis(NAP_TIME))?;
+    }
+}
+
+/// Play the credits roll for a session on its own,
+/// outside of the overlay.
+pub fn play_roll(stats: &crate::stats::Stats) -> Result<()> {
+    let window = Window::main(true)?;
+    window.no_delay(true)?;
+    window.set_cursor_visibility(Cursor::Hide)?;
+    setup_colors();
+
+    let mut roll_win = window.new_window(Pos::new(0, 0), window.size())?;
+    let mut roll = CreditsRoll::new(stats, roll_win.size());
+
+    while !roll.is_done {
+        if let Some(Input::Character('q')) = window.get_input() {
+            break;
+        }
 
+        roll_win.erase()?;
+        roll.draw(&mut roll_win)?;
+        roll_win.refresh()?;
         window.nap(Duration::from_millis(NAP_TIME))?;
     }
+
+    Ok(())
+}
+
+/// Preview an animation on repeat, with an optional text overlay,
+/// until `q` is pressed.
+pub fn preview_anim(path: &Path, size: Option<Size>, text: Option<&str>, name: &str) -> Result<()> {
+    // Fail before taking over the terminal
+    FrameAnim::validate(path)?;
+
+    let window = Window::main(true)?;
+    window.no_delay(true)?;
+    window.set_cursor_visibility(Cursor::Hide)?;
+    setup_colors();
+
+    let total = window.size();
+    let size = match size {
+        Some(size) => Size::new(size.width.min(total.width), size.height.min(total.height)),
+        None => total,
+    };
+    let mut preview_win = window.new_window(Pos::new(0, 0), size)?;
+    let params = [("name", name)];
+
+    loop {
+        let mut frame = FrameAnim::new(path, size.width as usize, &params)?;
+        let text_height = size.height - frame.height as i32;
+        let mut text = match text {
+            Some(text) if text_height > 0 => {
+                Some(CharAnim::new(text, Size::new(size.width, text_height), Animation::Scatter.into()))
+            }
+            _ => None,
+        };
+
+        if let Some(text) = &text {
+            frame.ttl = frame.ttl.max(text.ttl);
+        }
+
+        while !frame.is_done || text.as_ref().map(|t| !t.is_done).unwrap_or(false) {
+            if let Some(Input::Character('q')) = window.get_input() {
+                return Ok(());
+            }
+
+            preview_win.erase()?;
+            if let Some(text) = &mut text {
+                text.draw(&mut preview_win)?;
+            }
+
+            let lines = frame.update();
+            let y = size.height - lines.len() as i32;
+            preview_win.move_cursor(Pos::new(0, y - 1))?;
+            render_lines(lines, &preview_win, 0)?;
+
+            preview_win.refresh()?;
+            window.nap(Duration::from_millis(NAP_TIME))?;
+        }
+    }
 }
 
 fn render_lines(lines: Lines<'_>, window: &Window<Sub>, offset: usize) -> Result<()> {
@@ -1,5 +1,9 @@
 use neotwitch::IrcMessage;
 use anathema::Color;
+use serde::Deserialize;
+
+use crate::redemptions::Redemption;
+use crate::stats::Stats;
 
 #[derive(Debug, Clone)]
 pub enum DisplayMessage {
@@ -8,14 +12,90 @@ pub enum DisplayMessage {
     ClearChat,
     ChannelPoints(ChannelPointsMessage),
     TodoUpdate(String),
+    /// The todo file can't be read, with the reason
+    TodoUnavailable(String),
     Sub(Subscription, String),
+    GiftBomb(GiftBomb, String),
+    GiftBombRecipient { gifter: String, recipient: String },
     Follow(Vec<Follow>, String),
+    Raid(Raid, String),
+    Bits(Bits, String),
     Quote(String, Color),
+    Credits(Stats),
+    /// Switch to a named layout
+    Layout(String),
+    /// Live poll results, or the final result once finished
+    Poll(Poll),
+    /// Read out loud
+    Speak(Speech),
+    /// A new redemption waiting to be fulfilled
+    Redemption(Redemption),
 }
 
 #[derive(Debug, Clone)]
 pub struct Follow(pub String);
 
+#[derive(Debug, Clone)]
+pub struct Bits {
+    pub name: String,
+    pub amount: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Raid {
+    pub name: String,
+    pub viewers: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Speech {
+    pub text: String,
+    pub voice: String,
+}
+
+/// A Twitch poll or prediction, or a local `!vote` poll
+#[derive(Debug, Clone)]
+pub struct Poll {
+    pub title: String,
+    pub choices: Vec<PollChoice>,
+    /// Voting is over
+    pub finished: bool,
+    /// Index of the winning choice, if it isn't simply the one with the most votes
+    pub winner: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PollChoice {
+    pub title: String,
+    pub votes: usize,
+}
+
+impl Poll {
+    pub fn total(&self) -> usize {
+        self.choices.iter().map(|c| c.votes).sum()
+    }
+
+    /// Share of the votes, rounded to whole percent
+    pub fn percent(&self, choice: &PollChoice) -> usize {
+        match self.total() {
+            0 => 0,
+            total => (choice.votes * 100 + total / 2) / total,
+        }
+    }
+
+    /// The winning choice, none if nobody voted
+    pub fn winner(&self) -> Option<usize> {
+        if self.winner.is_some() {
+            return self.winner;
+        }
+        if self.total() == 0 {
+            return None;
+        }
+        // First choice wins a tie
+        self.choices.iter().enumerate().rev().max_by_key(|(_, c)| c.votes).map(|(i, _)| i)
+    }
+}
+
 // -----------------------------------------------------------------------------
 //     - Models -
 // -----------------------------------------------------------------------------
@@ -26,6 +106,14 @@ pub struct ChatMessage {
     pub message: String,
     pub color: Option<String>,
     pub action: bool,
+    pub highlight: Option<Highlight>,
+}
+
+/// Marks the first message of the session from someone worth noticing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Highlight {
+    FirstTime,
+    Returning,
 }
 
 impl From<IrcMessage> for ChatMessage {
@@ -36,6 +124,7 @@ impl From<IrcMessage> for ChatMessage {
             color: irc.tags.remove("color"),
             timestamp: irc.timestamp.format("%H:%M:%S").to_string(),
             action: irc.action,
+            highlight: None,
         }
     }
 }
@@ -48,6 +137,8 @@ pub struct ChannelPointsMessage {
     pub user: String,
     pub title: String,
     pub sound_path: Option<String>,
+    /// What the viewer typed, for rewards that ask for it
+    pub user_input: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -60,9 +151,24 @@ pub struct Subscription {
     pub sub_type: SubType,
     pub cumulative_months: Option<usize>,
     pub streak: Option<usize>,
+    // Months paid for up front
+    pub multi_month: Option<usize>,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// A community gift. The recipients arrive one at a time
+/// as `DisplayMessage::GiftBombRecipient`.
+#[derive(Debug, Clone)]
+pub struct GiftBomb {
+    pub gifter: String,
+    pub count: usize,
+    pub tier: Tier,
+    pub lifetime_total: Option<usize>,
+    // Recipients that arrived before the alert started
+    pub recipients: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Tier {
     Prime,
     One,
@@ -81,6 +187,16 @@ impl Tier {
             _ => Tier::Unknown,
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tier::Prime => "Prime",
+            Tier::One => "Tier 1",
+            Tier::Two => "Tier 2",
+            Tier::Three => "Tier 3",
+            Tier::Unknown => "Unknown",
+        }
+    }
 }
 
 // * sub
@@ -94,5 +210,6 @@ pub enum SubType {
     NewSub,
     Resub,
     Gift,
+    AnonGift,
     Unknown,
 }
@@ -0,0 +1,214 @@
+use std::f32::consts::TAU;
+use std::time::{Duration, Instant};
+
+use anathema::{Color, Colors, Pos, Size, Window};
+use anyhow::Result;
+use rand::prelude::*;
+
+use super::GREY;
+
+const CONFETTI: [char; 6] = ['*', '+', '~', 'o', '%', '#'];
+
+#[derive(Debug, Clone, Copy)]
+pub enum Celebration {
+    Confetti,
+    Fireworks,
+    /// Falling glyphs, like snow or emotes
+    Snow(char),
+}
+
+impl Celebration {
+    // Seconds between each emission
+    fn interval(&self) -> f32 {
+        match self {
+            Celebration::Confetti => 0.4,
+            Celebration::Fireworks => 0.6,
+            Celebration::Snow(_) => 0.3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Palette {
+    Rainbow,
+    Gold,
+    Ice,
+}
+
+impl Palette {
+    fn colors(&self) -> &'static [Color] {
+        match self {
+            Palette::Rainbow => &[Color::Red, Color::Yellow, Color::Green, Color::Cyan, Color::Blue, Color::Magenta],
+            Palette::Gold => &[Color::Yellow, Color::Red, Color::White],
+            Palette::Ice => &[Color::White, Color::Cyan, Color::Blue],
+        }
+    }
+
+    fn pick(&self, rng: &mut ThreadRng) -> Color {
+        *self.colors().choose(rng).expect("palettes are never empty")
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Particle -
+// -----------------------------------------------------------------------------
+struct Particle {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    gravity: f32,
+    glyph: char,
+    color: Color,
+    age: f32,
+    lifetime: f32,
+}
+
+impl Particle {
+    fn pos(&self) -> Pos {
+        Pos::new(self.x.round() as i32, self.y.round() as i32)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Particles -
+//     Positions and velocities are in cells and cells per second.
+// -----------------------------------------------------------------------------
+pub struct Particles {
+    celebration: Celebration,
+    palette: Palette,
+    particles: Vec<Particle>,
+    size: Size,
+    pub emit_for: Duration,
+    next_emit: f32,
+    started: Option<Instant>,
+    last_update: Option<Instant>,
+    pub is_done: bool,
+}
+
+impl Particles {
+    pub fn new(celebration: Celebration, palette: Palette, size: Size, emit_for: Duration) -> Self {
+        Self {
+            celebration,
+            palette,
+            particles: Vec::new(),
+            size,
+            emit_for,
+            next_emit: 0.0,
+            started: None,
+            last_update: None,
+            is_done: false,
+        }
+    }
+
+    fn emit(&mut self) {
+        let mut rng = thread_rng();
+        let width = self.size.width.max(1) as f32;
+        let height = self.size.height.max(1) as f32;
+
+        match self.celebration {
+            Celebration::Confetti => {
+                for _ in 0..(self.size.width / 3).max(1) {
+                    self.particles.push(Particle {
+                        x: rng.gen_range(0.0..width),
+                        y: 0.0,
+                        vx: rng.gen_range(-6.0..6.0),
+                        vy: rng.gen_range(0.0..4.0),
+                        gravity: 6.0,
+                        glyph: *CONFETTI.choose(&mut rng).expect("confetti is never empty"),
+                        color: self.palette.pick(&mut rng),
+                        age: 0.0,
+                        lifetime: rng.gen_range(3.0..5.0),
+                    });
+                }
+            }
+            Celebration::Fireworks => {
+                let x = rng.gen_range(width * 0.2..width * 0.8);
+                let y = rng.gen_range(height * 0.15..height * 0.5);
+                let color = self.palette.pick(&mut rng);
+                let count = 24;
+                for i in 0..count {
+                    let angle = TAU * i as f32 / count as f32;
+                    let speed = rng.gen_range(8.0..14.0);
+                    self.particles.push(Particle {
+                        x,
+                        y,
+                        // Cells are about twice as tall as they are wide
+                        vx: angle.cos() * speed * 2.0,
+                        vy: angle.sin() * speed,
+                        gravity: 10.0,
+                        glyph: '*',
+                        color,
+                        age: 0.0,
+                        lifetime: rng.gen_range(1.2..1.8),
+                    });
+                }
+            }
+            Celebration::Snow(glyph) => {
+                for _ in 0..(self.size.width / 10).max(1) {
+                    let vy = rng.gen_range(3.0..6.0);
+                    self.particles.push(Particle {
+                        x: rng.gen_range(0.0..width),
+                        y: 0.0,
+                        vx: rng.gen_range(-1.0..1.0),
+                        vy,
+                        gravity: 0.0,
+                        glyph,
+                        color: self.palette.pick(&mut rng),
+                        age: 0.0,
+                        lifetime: height / vy + 1.0,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let started = *self.started.get_or_insert(now);
+        let dt = self.last_update.map(|last| (now - last).as_secs_f32()).unwrap_or(0.0);
+        self.last_update = Some(now);
+
+        let elapsed = started.elapsed();
+        while elapsed < self.emit_for && self.next_emit <= elapsed.as_secs_f32() {
+            self.emit();
+            self.next_emit += self.celebration.interval();
+        }
+
+        let bottom = self.size.height as f32;
+        for p in &mut self.particles {
+            p.age += dt;
+            p.vy += p.gravity * dt;
+            p.x += p.vx * dt;
+            p.y += p.vy * dt;
+        }
+        self.particles.retain(|p| p.age < p.lifetime && p.y <= bottom);
+
+        self.is_done = elapsed >= self.emit_for && self.particles.is_empty();
+    }
+
+    pub fn draw<T>(&mut self, window: &mut Window<T>) -> Result<()> {
+        self.update();
+
+        for p in &self.particles {
+            let pos = p.pos();
+            if !window.contains(pos) {
+                continue;
+            }
+
+            // Dim particles towards the end of their life
+            let color = match p.age > p.lifetime * 0.75 {
+                true => GREY,
+                false => p.color,
+            };
+            let color_id: i16 = color.into();
+            window.set_color(Colors::get_color_pair(color_id as u32))?;
+            window.add_char_at(pos, p.glyph)?;
+        }
+
+        let reset = Colors::get_color_pair(0);
+        window.set_color(reset)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use anathema::{Attribute, Color, Colors, Input, Lines, Pos, Size, Sub, Window};
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+
+use super::models::DisplayMessage;
+use crate::redemptions::{DecisionTx, RedemptionStore, Status};
+
+/// Unfulfilled redemptions, oldest first.
+/// `n` and `p` select the next / previous one,
+/// `f` fulfils it and `x` rejects it (refunding the points).
+pub struct RedemptionDisplay {
+    window: Window<Sub>,
+    inner_win: Window<Sub>,
+    store: RedemptionStore,
+    decisions: DecisionTx,
+    selected: usize,
+    dirty: bool,
+}
+
+impl RedemptionDisplay {
+    pub fn new(window: Window<Sub>, store: RedemptionStore, decisions: DecisionTx) -> Result<Self> {
+        let parent_size = window.size();
+        let size = Size::new(parent_size.width - 2, parent_size.height - 2);
+        let inner_win = window.new_window(Pos::new(1, 1), size)?;
+        Ok(Self { window, inner_win, store, decisions, selected: 0, dirty: true })
+    }
+
+    pub fn touch(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn handle(&mut self, msg: &DisplayMessage) {
+        if let DisplayMessage::Redemption(redemption) = msg {
+            if let Err(e) = self.store.add(redemption.clone()) {
+                log::error!("Failed to store redemption {}: {}", redemption.id, e);
+            }
+            self.dirty = true;
+        }
+    }
+
+    pub fn input(&mut self, input: Input) {
+        let count = self.store.unfulfilled().count();
+        match input {
+            Input::Character('n') if self.selected + 1 < count => self.selected += 1,
+            Input::Character('p') if self.selected > 0 => self.selected -= 1,
+            Input::Character('f') => self.decide(Status::Fulfilled),
+            Input::Character('x') => self.decide(Status::Rejected),
+            _ => return,
+        }
+        self.dirty = true;
+    }
+
+    fn decide(&mut self, status: Status) {
+        let id = match self.store.unfulfilled().nth(self.selected) {
+            Some(redemption) => redemption.id.clone(),
+            None => return,
+        };
+
+        match self.store.decide(&id, status) {
+            Ok(decision) => {
+                if let Err(e) = self.decisions.send(decision) {
+                    log::error!("Failed to publish redemption {}: {}", id, e);
+                }
+            }
+            Err(e) => log::error!("Failed to update redemption {}: {}", id, e),
+        }
+
+        // Stay on the same row, unless it was the last one
+        let count = self.store.unfulfilled().count();
+        self.selected = self.selected.min(count.saturating_sub(1));
+    }
+
+    pub fn move_win(&mut self, pos: Pos) -> Result<()> {
+        self.window.move_win(pos)?;
+        self.inner_win.move_win(Pos::new(pos.x + 1, pos.y + 1))?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn resize(&mut self, size: Size) -> Result<()> {
+        self.window.resize(size)?;
+        self.inner_win.resize(Size::new(size.width - 2, size.height - 2))?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn update(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        self.dirty = false;
+        self.window.erase()?;
+        self.inner_win.erase()?;
+
+        let rows = self
+            .store
+            .unfulfilled()
+            .map(|r| match &r.user_input {
+                Some(input) => format!("{} {}: {} ({})", redeemed_at(r.redeemed), r.user, r.title, input),
+                None => format!("{} {}: {}", redeemed_at(r.redeemed), r.user, r.title),
+            })
+            .collect::<Vec<_>>();
+
+        let mut lines = Lines::new(self.inner_win.size().width as usize);
+        for (i, row) in rows.iter().enumerate() {
+            lines.reset_color();
+            lines.reset_style();
+            if i == self.selected {
+                if let Ok(col) = Colors::init_fg(Color::Yellow) {
+                    lines.color(col);
+                }
+                lines.style(Attribute::Bold);
+                lines.push_str("> ", true);
+            } else {
+                lines.push_str("  ", true);
+            }
+            lines.push_str(row, true);
+            lines.force_new_line();
+        }
+
+        // Keep the selected row in view, scrolling only once it's past the bottom
+        let offset = rows.len().saturating_sub(self.selected + 1);
+        super::render_lines(lines, &self.inner_win, offset)?;
+
+        self.window.draw_box();
+        let blue: i16 = Color::Blue.into();
+        self.window.set_color(Colors::get_color_pair(blue as u32))?;
+        self.window.print_at(Pos::new(2, 0), &format!(" Redemptions {} ", rows.len()))?;
+        self.window.set_color(Colors::get_color_pair(7))?;
+
+        self.window.refresh()?;
+        self.inner_win.refresh()?;
+
+        Ok(())
+    }
+}
+
+// Only the time for today's redemptions, the date as well for older ones
+fn redeemed_at(timestamp: i64) -> String {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    match Local.timestamp_opt(timestamp, 0).single() {
+        Some(time) if time.format("%Y-%m-%d").to_string() == today => time.format("%H:%M").to_string(),
+        Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+        None => "--:--".to_string(),
+    }
+}
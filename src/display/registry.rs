@@ -0,0 +1,152 @@
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use rand::prelude::*;
+use serde::Deserialize;
+
+use super::animation::FrameAnim;
+use super::models::{SubType, Subscription, Tier};
+
+const MANIFEST: &str = "animations.json";
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimEvent {
+    Sub,
+    Resub,
+    Gift,
+    Raid,
+}
+
+impl AnimEvent {
+    pub fn from_sub(sub: &Subscription) -> Self {
+        match (sub.gift, sub.sub_type) {
+            (true, _) | (_, SubType::Gift) | (_, SubType::AnonGift) => AnimEvent::Gift,
+            (_, SubType::Resub) => AnimEvent::Resub,
+            (_, SubType::NewSub) | (_, SubType::Unknown) => AnimEvent::Sub,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Manifest entry -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Deserialize)]
+struct Entry {
+    file: String,
+    events: Vec<AnimEvent>,
+    // No tiers means any tier
+    #[serde(default)]
+    tiers: Vec<Tier>,
+    // Only play on these cumulative months.
+    // No months means it's not a milestone animation.
+    #[serde(default)]
+    months: Vec<usize>,
+    #[serde(default = "default_weight")]
+    weight: u32,
+}
+
+impl Entry {
+    fn applies(&self, event: AnimEvent, tier: Tier) -> bool {
+        self.events.contains(&event) && (self.tiers.is_empty() || self.tiers.contains(&tier))
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Registry -
+// -----------------------------------------------------------------------------
+pub struct AnimationRegistry {
+    entries: Vec<(PathBuf, Entry)>,
+}
+
+impl AnimationRegistry {
+    /// Load the manifest from `dir` and validate every animation in it.
+    /// Animations that fail validation are logged and left out.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let manifest_path = dir.join(MANIFEST);
+        let raw = read_to_string(&manifest_path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", manifest_path.display(), e))?;
+        let manifest: Vec<Entry> = serde_json::from_str(&raw)?;
+
+        let mut entries = vec![];
+        for entry in manifest {
+            let path = dir.join(&entry.file);
+            match FrameAnim::validate(&path) {
+                Ok(()) => entries.push((path, entry)),
+                Err(e) => log::error!("Invalid animation {}: {}", path.display(), e),
+            }
+        }
+
+        for path in read_dir(dir)?.filter_map(|e| e.ok().map(|e| e.path())) {
+            let is_anim = path.extension().map(|ext| ext == "txt").unwrap_or(false);
+            if is_anim && !entries.iter().any(|(p, _)| *p == path) {
+                log::warn!("Animation is not in the manifest: {}", path.display());
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Pick a random animation, weighted, for an event.
+    /// Milestone animations win over regular ones when the months match.
+    pub fn pick(&self, event: AnimEvent, tier: Tier, months: Option<usize>) -> Option<&Path> {
+        let candidates = self.entries.iter().filter(|(_, e)| e.applies(event, tier));
+
+        let milestones = candidates
+            .clone()
+            .filter(|(_, e)| months.map(|m| e.months.contains(&m)).unwrap_or(false))
+            .collect::<Vec<_>>();
+
+        let pool = match milestones.is_empty() {
+            false => milestones,
+            true => candidates.filter(|(_, e)| e.months.is_empty()).collect(),
+        };
+
+        let mut rng = thread_rng();
+        pool.choose_weighted(&mut rng, |(_, e)| e.weight).ok().map(|(path, _)| path.as_path())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn registry() -> AnimationRegistry {
+        let manifest = r#"[
+            { "file": "prime.txt", "events": ["sub", "resub"], "tiers": ["prime"] },
+            { "file": "bender.txt", "events": ["sub", "resub", "gift"] },
+            { "file": "year.txt", "events": ["resub"], "months": [12] }
+        ]"#;
+        let entries = serde_json::from_str::<Vec<Entry>>(manifest).unwrap();
+        AnimationRegistry { entries: entries.into_iter().map(|e| (PathBuf::from(&e.file), e)).collect() }
+    }
+
+    #[test]
+    fn pick_by_tier() {
+        let registry = registry();
+        let path = registry.pick(AnimEvent::Gift, Tier::Prime, None);
+        assert_eq!(path, Some(Path::new("bender.txt")));
+    }
+
+    #[test]
+    fn milestone_wins() {
+        let registry = registry();
+        let path = registry.pick(AnimEvent::Resub, Tier::One, Some(12));
+        assert_eq!(path, Some(Path::new("year.txt")));
+
+        let path = registry.pick(AnimEvent::Resub, Tier::One, Some(11));
+        assert_eq!(path, Some(Path::new("bender.txt")));
+    }
+
+    #[test]
+    fn nothing_for_raids() {
+        let registry = registry();
+        assert!(registry.pick(AnimEvent::Raid, Tier::Unknown, None).is_none());
+    }
+}
@@ -0,0 +1,249 @@
+use std::time::{Duration, Instant};
+
+use super::models::DisplayMessage;
+
+/// Alert priorities, lowest first.
+/// The highest priority alert in the queue always plays next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Redemption,
+    Follow,
+    Bits,
+    Sub,
+    Raid,
+}
+
+/// Where an alert is played.
+/// Only one alert plays at a time, across all panels:
+/// a fullscreen alert would draw over the event panel,
+/// and two sounds at once is just noise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Panel {
+    Fullscreen,
+    Event,
+}
+
+/// Which panel plays a message, and how important it is.
+/// Anything that isn't an alert goes straight to the panels.
+pub fn classify(msg: &DisplayMessage) -> Option<(Priority, Panel)> {
+    match msg {
+        DisplayMessage::Raid(..) => Some((Priority::Raid, Panel::Fullscreen)),
+        DisplayMessage::Sub(..) | DisplayMessage::GiftBomb(..) => Some((Priority::Sub, Panel::Fullscreen)),
+        DisplayMessage::Bits(..) => Some((Priority::Bits, Panel::Event)),
+        DisplayMessage::Follow(..) => Some((Priority::Follow, Panel::Event)),
+        DisplayMessage::ChannelPoints(..) => Some((Priority::Redemption, Panel::Event)),
+        DisplayMessage::Chat(_)
+        | DisplayMessage::ChatEvent(_)
+        | DisplayMessage::ClearChat
+        | DisplayMessage::TodoUpdate(_)
+        | DisplayMessage::TodoUnavailable(_)
+        | DisplayMessage::GiftBombRecipient { .. }
+        | DisplayMessage::Quote(..)
+        | DisplayMessage::Credits(_)
+        | DisplayMessage::Layout(_)
+        | DisplayMessage::Poll(_)
+        | DisplayMessage::Speak(_)
+        | DisplayMessage::Redemption(_) => None,
+    }
+}
+
+// Try to fold `msg` into an alert that is already queued.
+// Gives the message back if they don't go together.
+fn merge(queued: &mut DisplayMessage, msg: DisplayMessage) -> Option<DisplayMessage> {
+    match (queued, msg) {
+        (DisplayMessage::Follow(follows, _), DisplayMessage::Follow(more, _)) => {
+            follows.extend(more);
+            None
+        }
+        (DisplayMessage::Sub(sub, _), DisplayMessage::Sub(more, _))
+            if sub.gift && more.gift && sub.display_name == more.display_name && sub.tier == more.tier =>
+        {
+            sub.recipients.extend(more.recipients);
+            None
+        }
+        (DisplayMessage::Bits(bits, _), DisplayMessage::Bits(more, _)) if bits.name == more.name => {
+            bits.amount += more.amount;
+            None
+        }
+        (DisplayMessage::GiftBomb(bomb, _), DisplayMessage::GiftBombRecipient { gifter, recipient })
+            if bomb.gifter == gifter && bomb.recipients.len() < bomb.count =>
+        {
+            bomb.recipients.push(recipient);
+            None
+        }
+        (_, msg) => Some(msg),
+    }
+}
+
+struct Queued {
+    priority: Priority,
+    panel: Panel,
+    msg: DisplayMessage,
+}
+
+// -----------------------------------------------------------------------------
+//     - Scheduler -
+// -----------------------------------------------------------------------------
+pub struct Scheduler {
+    queue: Vec<Queued>,
+    min_gap: Duration,
+    // When the panels were last seen busy
+    last_busy: Option<Instant>,
+    // The layout has no event panel
+    events_hidden: bool,
+}
+
+impl Scheduler {
+    pub fn new(min_gap: Duration) -> Self {
+        Self { queue: Vec::new(), min_gap, last_busy: None, events_hidden: false }
+    }
+
+    /// Event panel alerts stay queued while the panel is hidden
+    pub fn hide_events(&mut self, hidden: bool) {
+        self.events_hidden = hidden;
+    }
+
+    /// Queue an alert, merging it with a queued one if possible.
+    /// Returns the message if it isn't an alert, or if it
+    /// couldn't be merged and has to go to the panels as is,
+    /// like a gift bomb recipient for a bomb that is already playing.
+    pub fn push(&mut self, msg: DisplayMessage) -> Option<DisplayMessage> {
+        let mut msg = msg;
+        for queued in self.queue.iter_mut() {
+            msg = merge(&mut queued.msg, msg)?;
+        }
+
+        match classify(&msg) {
+            Some((priority, panel)) => {
+                self.queue.push(Queued { priority, panel, msg });
+                None
+            }
+            None => Some(msg),
+        }
+    }
+
+    /// The next alert to play, if the panels are free
+    /// and the gap since the last alert has passed.
+    /// Alerts of the same priority play in the order they arrived.
+    pub fn next(&mut self, busy: bool, now: Instant) -> Option<(Panel, DisplayMessage)> {
+        if busy {
+            self.last_busy = Some(now);
+            return None;
+        }
+
+        if let Some(last_busy) = self.last_busy {
+            if now.saturating_duration_since(last_busy) < self.min_gap {
+                return None;
+            }
+        }
+
+        let (index, _) = self
+            .queue
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, queued)| !(self.events_hidden && queued.panel == Panel::Event))
+            .max_by_key(|(_, queued)| queued.priority)?;
+
+        let queued = self.queue.remove(index);
+        // Count the alert as playing right away, so the gap
+        // doesn't start until it's done
+        self.last_busy = Some(now);
+        Some((queued.panel, queued.msg))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::display::models::{Bits, ChannelPointsMessage, Follow, GiftBomb, Raid, Tier};
+
+    fn follow(name: &str) -> DisplayMessage {
+        DisplayMessage::Follow(vec![Follow(name.to_string())], String::new())
+    }
+
+    fn redemption() -> DisplayMessage {
+        let msg = ChannelPointsMessage { user: "a".into(), title: "b".into(), sound_path: None, user_input: None };
+        DisplayMessage::ChannelPoints(msg)
+    }
+
+    fn raid() -> DisplayMessage {
+        DisplayMessage::Raid(Raid { name: "raider".into(), viewers: 5 }, String::new())
+    }
+
+    #[test]
+    fn highest_priority_first() {
+        let mut scheduler = Scheduler::new(Duration::ZERO);
+        scheduler.push(redemption());
+        scheduler.push(follow("a"));
+        scheduler.push(raid());
+
+        let now = Instant::now();
+        assert!(matches!(scheduler.next(false, now), Some((Panel::Fullscreen, DisplayMessage::Raid(..)))));
+        assert!(matches!(scheduler.next(false, now), Some((Panel::Event, DisplayMessage::Follow(..)))));
+        assert!(matches!(scheduler.next(false, now), Some((Panel::Event, DisplayMessage::ChannelPoints(..)))));
+        assert!(scheduler.next(false, now).is_none());
+    }
+
+    #[test]
+    fn same_priority_in_order() {
+        let mut scheduler = Scheduler::new(Duration::ZERO);
+        scheduler.push(DisplayMessage::Bits(Bits { name: "a".into(), amount: 1 }, String::new()));
+        scheduler.push(DisplayMessage::Bits(Bits { name: "b".into(), amount: 1 }, String::new()));
+
+        match scheduler.next(false, Instant::now()) {
+            Some((_, DisplayMessage::Bits(bits, _))) => assert_eq!(bits.name, "a"),
+            _ => panic!("expected bits"),
+        }
+    }
+
+    #[test]
+    fn wait_for_busy_panels_and_gap() {
+        let mut scheduler = Scheduler::new(Duration::from_secs(2));
+        scheduler.push(follow("a"));
+
+        let now = Instant::now();
+        assert!(scheduler.next(true, now).is_none());
+        assert!(scheduler.next(false, now + Duration::from_secs(1)).is_none());
+        assert!(scheduler.next(false, now + Duration::from_secs(2)).is_some());
+    }
+
+    #[test]
+    fn keep_alerts_for_hidden_events() {
+        let mut scheduler = Scheduler::new(Duration::ZERO);
+        scheduler.push(follow("a"));
+        scheduler.hide_events(true);
+
+        let now = Instant::now();
+        assert!(scheduler.next(false, now).is_none());
+        scheduler.hide_events(false);
+        assert!(matches!(scheduler.next(false, now), Some((Panel::Event, _))));
+    }
+
+    #[test]
+    fn merge_follows() {
+        let mut scheduler = Scheduler::new(Duration::ZERO);
+        scheduler.push(follow("a"));
+        scheduler.push(follow("b"));
+
+        let now = Instant::now();
+        match scheduler.next(false, now) {
+            Some((_, DisplayMessage::Follow(follows, _))) => assert_eq!(follows.len(), 2),
+            _ => panic!("expected follows"),
+        }
+        assert!(scheduler.next(false, now).is_none());
+    }
+
+    #[test]
+    fn recipients_join_queued_bomb() {
+        let mut scheduler = Scheduler::new(Duration::ZERO);
+        let bomb = GiftBomb { gifter: "a".into(), count: 2, tier: Tier::One, lifetime_total: None, recipients: vec![] };
+        scheduler.push(DisplayMessage::GiftBomb(bomb, String::new()));
+
+        let recipient = |name: &str| DisplayMessage::GiftBombRecipient { gifter: "a".into(), recipient: name.into() };
+        assert!(scheduler.push(recipient("b")).is_none());
+        assert!(scheduler.push(recipient("c")).is_none());
+        // The bomb is full
+        assert!(scheduler.push(recipient("d")).is_some());
+    }
+}
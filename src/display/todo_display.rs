@@ -0,0 +1,141 @@
+use anathema::{Attribute, Color, Colors, Lines, Pos, Size, Sub, Window};
+use anyhow::Result;
+
+use super::models::DisplayMessage;
+use crate::todo::{Todo, TodoLine};
+
+pub struct TodoDisplay {
+    window: Window<Sub>,
+    inner_win: Window<Sub>,
+    todo: Todo,
+    // Why the todo can't be shown, if it can't
+    unavailable: Option<String>,
+    dirty: bool,
+}
+
+impl TodoDisplay {
+    pub fn new(window: Window<Sub>) -> Result<Self> {
+        let parent_size = window.size();
+        let size = Size::new(parent_size.width - 2, parent_size.height - 2);
+        let inner_win = window.new_window(Pos::new(1, 1), size)?;
+        Ok(Self { window, inner_win, todo: Todo::default(), unavailable: None, dirty: true })
+    }
+
+    pub fn touch(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn handle(&mut self, msg: &DisplayMessage) {
+        match msg {
+            DisplayMessage::TodoUpdate(src) => {
+                self.todo = Todo::parse(src);
+                self.unavailable = None;
+                self.dirty = true;
+            }
+            DisplayMessage::TodoUnavailable(reason) => {
+                self.unavailable = Some(reason.clone());
+                self.dirty = true;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn move_win(&mut self, pos: Pos) -> Result<()> {
+        self.window.move_win(pos)?;
+        self.inner_win.move_win(Pos::new(pos.x + 1, pos.y + 1))?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn resize(&mut self, size: Size) -> Result<()> {
+        self.window.resize(size)?;
+        self.inner_win.resize(Size::new(size.width - 2, size.height - 2))?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn update(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        self.dirty = false;
+        self.window.erase()?;
+        self.inner_win.erase()?;
+
+        let mut lines = Lines::new(self.inner_win.size().width as usize);
+
+        // Keep showing the last known todo below the reason
+        if let Some(reason) = &self.unavailable {
+            if let Ok(col) = Colors::init_fg(Color::Red) {
+                lines.color(col);
+            }
+            lines.push_str("Todo unavailable:", true);
+            lines.pad(1);
+            lines.push_str(reason, true);
+            lines.force_new_line();
+        }
+
+        // Numbered like `!todo done <n>`
+        let mut number = 0;
+        let mut numbers = vec![];
+        for line in &self.todo.lines {
+            if let TodoLine::Item { .. } = line {
+                number += 1;
+                numbers.push(format!("{}.", number));
+            }
+        }
+        let mut numbers = numbers.iter();
+
+        for line in &self.todo.lines {
+            lines.reset_color();
+            lines.reset_style();
+
+            match line {
+                TodoLine::Heading(_, text) => {
+                    if let Ok(col) = Colors::init_fg(Color::Cyan) {
+                        lines.color(col);
+                    }
+                    lines.style(Attribute::Bold);
+                    lines.push_str(text, true);
+                }
+                TodoLine::Item { done: false, text } => {
+                    lines.push_str(numbers.next().expect("one number per item"), true);
+                    lines.pad(1);
+                    lines.push_str("[ ]", true);
+                    lines.pad(1);
+                    lines.push_str(text, true);
+                }
+                TodoLine::Item { done: true, text } => {
+                    if let Ok(col) = Colors::init_fg(super::GREY) {
+                        lines.color(col);
+                    }
+                    lines.push_str(numbers.next().expect("one number per item"), true);
+                    lines.pad(1);
+                    lines.push_str("[x]", true);
+                    lines.pad(1);
+                    lines.push_str(text, true);
+                }
+                TodoLine::Text(text) => lines.push_str(text, true),
+            }
+
+            lines.force_new_line();
+        }
+
+        // Offset by the number of lines to always show the top of the list
+        let offset = lines.len();
+        super::render_lines(lines, &self.inner_win, offset)?;
+
+        self.window.draw_box();
+        let blue: i16 = Color::Blue.into();
+        self.window.set_color(Colors::get_color_pair(blue as u32))?;
+        let (done, total) = self.todo.progress();
+        self.window.print_at(Pos::new(2, 0), &format!(" Todo {}/{} ", done, total))?;
+        self.window.set_color(Colors::get_color_pair(7))?;
+
+        self.window.refresh()?;
+        self.inner_win.refresh()?;
+
+        Ok(())
+    }
+}
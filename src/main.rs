@@ -1,11 +1,20 @@
 use neotwitch::{ChannelPoints, FollowEvent, Irc, IrcMessage, SubscribeEvent};
 
+mod anim;
 mod audio;
+mod chat_out;
+mod config;
+mod control;
 mod transformers;
 mod twitch;
 mod display;
+mod quotes;
+mod redemptions;
+mod router;
+mod stats;
 mod testdata;
 mod todo;
+mod viewers;
 
 pub type EventSender = tokio::sync::mpsc::Sender<Event>;
 pub type EventReceiver = tokio::sync::mpsc::Receiver<Event>;
@@ -15,6 +24,7 @@ pub enum Event {
     ChatEvent(String),
     ClearChat,
     Twitch(twitch::Twitch),
+    Control(control::Control),
     Quit,
 }
 
@@ -61,10 +71,40 @@ async fn main() {
             "substreak" => testdata::sub_streak().await,
             "giftsub" => testdata::gift_sub().await,
             "anongiftsub" => testdata::anon_gift_sub().await,
+            "giftbomb" => testdata::gift_bomb().await,
             "oslash" => testdata::oslash().await,
             "follow" => testdata::follow().await,
             "chat" => testdata::chat().await,
             "action" => testdata::action().await,
+            "anim" => {
+                if let Err(e) = anim::run(&mut args) {
+                    eprintln!("{}", e);
+                }
+            }
+            "credits" => control::send(control::Control::Credits).await,
+            "raidmode" => match args.next().as_deref() {
+                Some("on") => control::send(control::Control::RaidMode(true)).await,
+                Some("off") => control::send(control::Control::RaidMode(false)).await,
+                _ => eprintln!("Usage: raidmode <on|off>"),
+            },
+            "layout" => match args.next() {
+                Some(name) => control::send(control::Control::Layout(name)).await,
+                None => eprintln!("Usage: layout <name>"),
+            },
+            "summary" => {
+                let mut roll = false;
+                let mut path = None;
+                while let Some(arg) = args.next() {
+                    match arg.as_ref() {
+                        "--roll" => roll = true,
+                        _ => path = Some(arg),
+                    }
+                }
+
+                if let Err(e) = stats::summary(path, roll) {
+                    eprintln!("Summary failed: {}", e);
+                }
+            }
             _ => {}
         }
     }
@@ -73,14 +113,33 @@ async fn main() {
         return;
     }
 
+    let config = match config::Config::load(config::CONFIG_PATH) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
     let (tx, rx) = tokio::sync::mpsc::channel(100);
     let (display_tx, display_rx) = display::channel();
-
-    tokio::spawn(transformers::run(rx, display_tx.clone()));
-    tokio::spawn(todo::watch_todo(display_tx, "/home/togglebit/wiki/todo.md"));
+    let (chat_tx, chat_rx) = chat_out::channel();
+    let (decision_tx, decision_rx) = redemptions::channel();
+
+    let stats_path = stats::session_path();
+
+    tokio::spawn(chat_out::run(chat_rx, config.chat_out.clone()));
+    tokio::spawn(redemptions::publish(decision_rx));
+    tokio::spawn(transformers::run(rx, display_tx.clone(), chat_tx, stats_path, config.clone()));
+    let todo_path = config.todo_path.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = todo::watch_todo(display_tx, todo_path) {
+            log::error!("Stopped watching the todo: {}", e);
+        }
+    });
     tokio::spawn(twitch::start(tx.clone()));
 
-    if let Err(e) = display::run(display_rx) {
+    if let Err(e) = display::run(display_rx, tx, config, decision_tx) {
         eprintln!("Fail: {}", e);
     }
 }
@@ -0,0 +1,137 @@
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Something worth remembering, said on stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quote {
+    pub id: usize,
+    pub text: String,
+    pub added_by: String,
+    pub added: String,
+    /// The game or whatever was going on at the time
+    #[serde(default)]
+    pub game: Option<String>,
+}
+
+impl Quote {
+    /// `"text" #id (game, date)`
+    pub fn display(&self) -> String {
+        let date = self.added.split(' ').next().unwrap_or(&self.added);
+        match &self.game {
+            Some(game) => format!("\"{}\" #{} ({}, {})", self.text, self.id, game, date),
+            None => format!("\"{}\" #{} ({})", self.text, self.id, date),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Quote book -
+//     All the quotes, kept in a json file.
+//     Ids are never reused.
+// -----------------------------------------------------------------------------
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QuoteBook {
+    quotes: Vec<Quote>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl QuoteBook {
+    /// Load the quotes, or start an empty book if the file doesn't exist yet
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut book = match path.exists() {
+            true => {
+                let raw = read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+                serde_json::from_str::<Self>(&raw)?
+            }
+            false => Self::default(),
+        };
+        book.path = path.to_path_buf();
+        Ok(book)
+    }
+
+    fn save(&self) -> Result<()> {
+        write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, text: &str, added_by: &str, game: Option<&str>) -> Result<&Quote> {
+        let quote = Quote {
+            id: self.quotes.last().map(|q| q.id + 1).unwrap_or(1),
+            text: text.to_string(),
+            added_by: added_by.to_string(),
+            added: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            game: game.map(str::to_string),
+        };
+        self.quotes.push(quote);
+        // Don't keep a quote that isn't on disk
+        if let Err(e) = self.save() {
+            self.quotes.pop();
+            return Err(e);
+        }
+        Ok(&self.quotes[self.quotes.len() - 1])
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Quote> {
+        self.quotes.iter().find(|q| q.id == id)
+    }
+
+    pub fn random(&self) -> Option<&Quote> {
+        self.quotes.choose(&mut thread_rng())
+    }
+
+    /// Quotes containing all the words, ignoring case
+    pub fn search(&self, text: &str) -> Vec<&Quote> {
+        let words = text.to_lowercase();
+        let words = words.split_whitespace().collect::<Vec<_>>();
+        self.quotes
+            .iter()
+            .filter(|q| {
+                let haystack = format!("{} {}", q.text, q.game.as_deref().unwrap_or("")).to_lowercase();
+                words.iter().all(|w| haystack.contains(w))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn book() -> QuoteBook {
+        let path = std::env::temp_dir().join(format!("witchbox-quotes-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        QuoteBook::load(path).unwrap()
+    }
+
+    #[test]
+    fn add_and_search() {
+        let mut book = book();
+        assert_eq!(book.add("It compiled first try", "a", Some("Rust")).unwrap().id, 1);
+        assert_eq!(book.add("Who wrote this code", "b", None).unwrap().id, 2);
+
+        assert_eq!(book.get(2).unwrap().added_by, "b");
+        assert!(book.get(3).is_none());
+
+        let found = book.search("COMPILED rust");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, 1);
+        assert!(book.search("python").is_empty());
+
+        let reloaded = QuoteBook::load(&book.path).unwrap();
+        assert_eq!(reloaded.quotes.len(), 2);
+        let _ = std::fs::remove_file(&book.path);
+    }
+
+    #[test]
+    fn failed_save_drops_quote() {
+        let mut book = QuoteBook::load(std::env::temp_dir().join("no-such-dir").join("quotes.json")).unwrap();
+        assert!(book.add("Lost", "a", None).is_err());
+        assert!(book.quotes.is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::router::Publisher;
+
+/// Decisions are published on this channel, for a bot on the router
+/// to complete or refund the redemption through the Twitch API.
+pub const CHANNEL: &[u8] = b"redemptions";
+
+// Fulfilled and rejected redemptions older than this are dropped from the store
+const KEEP_DONE: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Unfulfilled,
+    Fulfilled,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Redemption {
+    pub id: String,
+    pub reward_id: String,
+    pub user: String,
+    pub title: String,
+    pub user_input: Option<String>,
+    // Unix timestamp
+    pub redeemed: i64,
+    pub status: Status,
+}
+
+/// What gets published when a redemption is fulfilled or rejected
+#[derive(Debug, Clone, Serialize)]
+pub struct Decision {
+    pub id: String,
+    pub reward_id: String,
+    pub status: Status,
+}
+
+// -----------------------------------------------------------------------------
+//     - Store -
+//     Every redemption and what happened to it, kept in a json file.
+// -----------------------------------------------------------------------------
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RedemptionStore {
+    redemptions: Vec<Redemption>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl RedemptionStore {
+    /// Load the store, or start an empty one if the file doesn't exist yet
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut store = match path.exists() {
+            true => {
+                let raw = read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+                serde_json::from_str::<Self>(&raw)?
+            }
+            false => Self::default(),
+        };
+        store.path = path.to_path_buf();
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, redemption: Redemption) -> Result<()> {
+        // The same redemption can be delivered twice
+        if self.redemptions.iter().any(|r| r.id == redemption.id) {
+            return Ok(());
+        }
+        self.redemptions.push(redemption);
+
+        let done = self.redemptions.iter().filter(|r| r.status != Status::Unfulfilled).count();
+        let mut excess = done.saturating_sub(KEEP_DONE);
+        self.redemptions.retain(|r| {
+            let keep = excess == 0 || r.status == Status::Unfulfilled;
+            if !keep {
+                excess -= 1;
+            }
+            keep
+        });
+
+        self.save()
+    }
+
+    /// Oldest first
+    pub fn unfulfilled(&self) -> impl Iterator<Item = &Redemption> {
+        self.redemptions.iter().filter(|r| r.status == Status::Unfulfilled)
+    }
+
+    pub fn decide(&mut self, id: &str, status: Status) -> Result<Decision> {
+        let index = self.redemptions.iter().position(|r| r.id == id).ok_or_else(|| anyhow!("No redemption {}", id))?;
+        let previous = std::mem::replace(&mut self.redemptions[index].status, status);
+
+        // Keep it unfulfilled if the decision can't be saved, so it can be decided again
+        if let Err(e) = self.save() {
+            self.redemptions[index].status = previous;
+            return Err(e);
+        }
+
+        let redemption = &self.redemptions[index];
+        Ok(Decision { id: redemption.id.clone(), reward_id: redemption.reward_id.clone(), status })
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Publishing decisions -
+// -----------------------------------------------------------------------------
+pub type DecisionTx = UnboundedSender<Decision>;
+pub type DecisionRx = UnboundedReceiver<Decision>;
+
+pub fn channel() -> (DecisionTx, DecisionRx) {
+    unbounded_channel()
+}
+
+pub async fn publish(mut rx: DecisionRx) {
+    let mut publisher = Publisher::new(CHANNEL);
+
+    while let Some(decision) = rx.recv().await {
+        let payload = match serde_json::to_vec(&decision) {
+            Ok(payload) => payload,
+            Err(e) => {
+                log::error!("Failed to serialize decision: {}", e);
+                continue;
+            }
+        };
+
+        if let Err(e) = publisher.send(&payload).await {
+            log::error!("Redemption {} not published: {}", decision.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn redemption(id: &str) -> Redemption {
+        Redemption {
+            id: id.to_string(),
+            reward_id: "reward".to_string(),
+            user: "a".to_string(),
+            title: "hydrate! (maybe)".to_string(),
+            user_input: None,
+            redeemed: 0,
+            status: Status::Unfulfilled,
+        }
+    }
+
+    #[test]
+    fn decide() {
+        let path = std::env::temp_dir().join(format!("witchbox-redemptions-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut store = RedemptionStore::load(&path).unwrap();
+
+        store.add(redemption("1")).unwrap();
+        store.add(redemption("2")).unwrap();
+        store.add(redemption("1")).unwrap();
+        assert_eq!(store.unfulfilled().count(), 2);
+
+        let decision = store.decide("1", Status::Rejected).unwrap();
+        assert_eq!(decision.status, Status::Rejected);
+        assert!(store.decide("3", Status::Fulfilled).is_err());
+
+        let store = RedemptionStore::load(&path).unwrap();
+        assert_eq!(store.unfulfilled().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["2"]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn failed_save_keeps_status() {
+        let path = std::env::temp_dir().join("no-such-dir").join("redemptions.json");
+        let mut store = RedemptionStore { redemptions: vec![redemption("1")], path };
+        assert!(store.decide("1", Status::Fulfilled).is_err());
+        assert_eq!(store.unfulfilled().count(), 1);
+    }
+}
@@ -0,0 +1,38 @@
+use anyhow::{anyhow, Result};
+use tinyroute::client::{connect, ClientMessage, ClientReceiver, ClientSender, TcpClient};
+
+const ROUTER_ADDRESS: &str = "127.0.0.1:6000";
+
+// -----------------------------------------------------------------------------
+//     - Publisher -
+//     Sends payloads on a router channel.
+//     Connects on the first send, and again after a failed send.
+// -----------------------------------------------------------------------------
+pub struct Publisher {
+    channel: Vec<u8>,
+    client: Option<(ClientSender, ClientReceiver)>,
+}
+
+impl Publisher {
+    pub fn new(channel: impl Into<Vec<u8>>) -> Self {
+        Self { channel: channel.into(), client: None }
+    }
+
+    pub async fn send(&mut self, payload: &[u8]) -> Result<()> {
+        if self.client.is_none() {
+            let client = TcpClient::connect(ROUTER_ADDRESS)
+                .await
+                .map_err(|e| anyhow!("Failed to connect to the router: {}", e))?;
+            self.client = Some(connect(client, None));
+        }
+
+        if let Some((tx, _rx)) = &self.client {
+            if let Err(e) = tx.send(ClientMessage::channel_payload(&self.channel, payload)) {
+                self.client = None;
+                return Err(anyhow!("Failed to send to the router: {}", e));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read_dir, read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::display::models::{SubType, Subscription};
+
+const SESSION_DIR: &str = "sessions";
+const TOP_CHATTERS: usize = 10;
+
+/// Path to the stats file for a session starting now.
+pub fn session_path() -> PathBuf {
+    let name = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S.json").to_string();
+    Path::new(SESSION_DIR).join(name)
+}
+
+/// The most recent session file, if there is one.
+/// Session files are named by their start time so the
+/// last one sorted by name is the latest.
+pub fn latest_session() -> Option<PathBuf> {
+    read_dir(SESSION_DIR)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+        .max()
+}
+
+// -----------------------------------------------------------------------------
+//     - Sections -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub title: String,
+    pub entries: Vec<String>,
+}
+
+impl Section {
+    fn new(title: impl Into<String>, entries: Vec<String>) -> Self {
+        Self { title: title.into(), entries }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Stats -
+// -----------------------------------------------------------------------------
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct SubCounts {
+    pub new: usize,
+    pub resub: usize,
+    pub gifted: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stats {
+    pub started: String,
+    pub chatters: BTreeMap<String, usize>,
+    pub subs: BTreeMap<String, SubCounts>,
+    pub subscribers: Vec<String>,
+    pub gifters: BTreeMap<String, usize>,
+    pub followers: Vec<String>,
+    pub redemptions: BTreeMap<String, usize>,
+    pub bits: BTreeMap<String, usize>,
+    #[serde(default)]
+    pub raiders: BTreeMap<String, usize>,
+    // Follows that never got an alert, with the reason.
+    // Only in the report, never in the credits.
+    #[serde(default)]
+    pub suppressed_follows: Vec<(String, String)>,
+    // Chatted for the first time ever this session
+    #[serde(default)]
+    pub first_chatters: Vec<String>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            started: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            chatters: BTreeMap::new(),
+            subs: BTreeMap::new(),
+            subscribers: Vec::new(),
+            gifters: BTreeMap::new(),
+            followers: Vec::new(),
+            redemptions: BTreeMap::new(),
+            bits: BTreeMap::new(),
+            raiders: BTreeMap::new(),
+            suppressed_follows: Vec::new(),
+            first_chatters: Vec::new(),
+            dirty: false,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let stats = serde_json::from_str(&raw)?;
+        Ok(stats)
+    }
+
+    /// Write the stats to disk, but only if anything changed
+    /// since the last save.
+    pub fn save(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        write(path, serde_json::to_string_pretty(self)?)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn chat(&mut self, nick: &str) {
+        *self.chatters.entry(nick.to_string()).or_default() += 1;
+        self.dirty = true;
+    }
+
+    pub fn sub(&mut self, sub: &Subscription) {
+        let counts = self.subs.entry(sub.tier.name().to_string()).or_default();
+        let name = sub.display_name.as_deref().unwrap_or("[Anonymous]");
+
+        if sub.gift {
+            counts.gifted += sub.recipients.len();
+            *self.gifters.entry(name.to_string()).or_default() += sub.recipients.len();
+            self.subscribers.extend(sub.recipients.iter().cloned());
+        } else {
+            match sub.sub_type {
+                SubType::Resub => counts.resub += 1,
+                SubType::Gift | SubType::AnonGift => counts.gifted += 1,
+                SubType::NewSub | SubType::Unknown => counts.new += 1,
+            }
+            self.subscribers.push(name.to_string());
+        }
+
+        self.dirty = true;
+    }
+
+    /// Everyone who followed, alerted or not. Each name is only counted once.
+    pub fn follow(&mut self, name: &str) {
+        if self.followers.iter().any(|f| f == name) {
+            return;
+        }
+        self.followers.push(name.to_string());
+        self.dirty = true;
+    }
+
+    pub fn suppressed_follow(&mut self, name: &str, reason: &str) {
+        self.suppressed_follows.push((name.to_string(), reason.to_string()));
+        self.dirty = true;
+    }
+
+    pub fn first_chat(&mut self, nick: &str) {
+        self.first_chatters.push(nick.to_string());
+        self.dirty = true;
+    }
+
+    pub fn redemption(&mut self, title: &str) {
+        *self.redemptions.entry(title.to_string()).or_default() += 1;
+        self.dirty = true;
+    }
+
+    pub fn bits(&mut self, user: &str, amount: usize) {
+        *self.bits.entry(user.to_string()).or_default() += amount;
+        self.dirty = true;
+    }
+
+    pub fn raid(&mut self, name: &str, viewers: usize) {
+        *self.raiders.entry(name.to_string()).or_default() += viewers;
+        self.dirty = true;
+    }
+
+    pub fn top_chatters(&self, count: usize) -> Vec<(&str, usize)> {
+        let mut chatters = self.chatters.iter().map(|(nick, c)| (nick.as_str(), *c)).collect::<Vec<_>>();
+        chatters.sort_by(|a, b| b.1.cmp(&a.1));
+        chatters.truncate(count);
+        chatters
+    }
+
+    /// The stats split into titled sections, in the order
+    /// they should be shown in a report or credits roll.
+    /// Empty sections are left out.
+    pub fn sections(&self) -> Vec<Section> {
+        let mut sections = vec![];
+
+        if !self.followers.is_empty() {
+            sections.push(Section::new("Followers", self.followers.clone()));
+        }
+
+        if !self.subs.is_empty() {
+            let mut entries = self
+                .subs
+                .iter()
+                .map(|(tier, c)| format!("{}: {} new, {} resubs, {} gifted", tier, c.new, c.resub, c.gifted))
+                .collect::<Vec<_>>();
+            entries.extend(self.subscribers.iter().cloned());
+            sections.push(Section::new("Subscribers", entries));
+        }
+
+        if !self.gifters.is_empty() {
+            let entries = self.gifters.iter().map(|(name, count)| format!("{} ({})", name, count)).collect();
+            sections.push(Section::new("Gifters", entries));
+        }
+
+        if !self.raiders.is_empty() {
+            let entries = self.raiders.iter().map(|(name, viewers)| format!("{} ({} raiders)", name, viewers)).collect();
+            sections.push(Section::new("Raiders", entries));
+        }
+
+        if !self.bits.is_empty() {
+            let entries = self.bits.iter().map(|(name, bits)| format!("{}: {} bits", name, bits)).collect();
+            sections.push(Section::new("Cheers", entries));
+        }
+
+        if !self.redemptions.is_empty() {
+            let entries = self.redemptions.iter().map(|(title, count)| format!("{} x{}", title, count)).collect();
+            sections.push(Section::new("Redemptions", entries));
+        }
+
+        if !self.chatters.is_empty() {
+            let entries = self
+                .top_chatters(TOP_CHATTERS)
+                .into_iter()
+                .map(|(nick, count)| format!("{}: {} messages", nick, count))
+                .collect();
+            sections.push(Section::new("Top chatters", entries));
+        }
+
+        if !self.first_chatters.is_empty() {
+            sections.push(Section::new("First time chatters", self.first_chatters.clone()));
+        }
+
+        sections
+    }
+
+    /// A plain text, credits style report
+    pub fn report(&self) -> String {
+        let mut report = format!("~ Stream of {} ~\n", self.started);
+        let total_messages = self.chatters.values().sum::<usize>();
+        report.push_str(&format!("{} messages from {} chatters\n", total_messages, self.chatters.len()));
+
+        for section in self.sections() {
+            report.push_str(&format!("\n{}\n", section.title));
+            for entry in section.entries {
+                report.push_str(&format!("    {}\n", entry));
+            }
+        }
+
+        if !self.suppressed_follows.is_empty() {
+            report.push_str("\nSuppressed follows\n");
+            for (name, reason) in &self.suppressed_follows {
+                report.push_str(&format!("    {} ({})\n", name, reason));
+            }
+        }
+
+        report
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Summary subcommand -
+// -----------------------------------------------------------------------------
+pub fn summary(path: Option<String>, roll: bool) -> Result<()> {
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => latest_session().ok_or_else(|| anyhow!("No sessions in {}", SESSION_DIR))?,
+    };
+
+    let stats = Stats::load(path)?;
+
+    match roll {
+        true => crate::display::play_roll(&stats),
+        false => {
+            println!("{}", stats.report());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::display::models::Tier;
+
+    fn gift(recipients: &[&str]) -> Subscription {
+        Subscription {
+            gift: true,
+            display_name: Some("gifter".into()),
+            recipients: recipients.iter().map(|r| r.to_string()).collect(),
+            tier: Tier::One,
+            message: String::new(),
+            sub_type: SubType::Gift,
+            cumulative_months: None,
+            streak: None,
+            multi_month: None,
+        }
+    }
+
+    #[test]
+    fn count_gifts_per_recipient() {
+        let mut stats = Stats::new();
+        stats.sub(&gift(&["a", "b", "c"]));
+        assert_eq!(stats.subs["Tier 1"].gifted, 3);
+        assert_eq!(stats.gifters["gifter"], 3);
+        assert_eq!(stats.subscribers.len(), 3);
+    }
+
+    #[test]
+    fn top_chatters_sorted() {
+        let mut stats = Stats::new();
+        stats.chat("a");
+        stats.chat("b");
+        stats.chat("b");
+        assert_eq!(stats.top_chatters(1), vec![("b", 2)]);
+    }
+
+    #[test]
+    fn count_followers_once() {
+        let mut stats = Stats::new();
+        stats.follow("a");
+        stats.follow("a");
+        stats.suppressed_follow("a", "duplicate");
+        assert_eq!(stats.followers, vec!["a".to_string()]);
+        assert_eq!(stats.suppressed_follows.len(), 1);
+    }
+
+    #[test]
+    fn only_save_when_dirty() {
+        let mut stats = Stats::new();
+        let path = std::env::temp_dir().join(format!("witchbox-stats-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        stats.save(&path).unwrap();
+        assert!(!path.exists());
+        stats.follow("someone");
+        stats.save(&path).unwrap();
+        assert_eq!(Stats::load(&path).unwrap().followers, vec!["someone".to_string()]);
+        let _ = std::fs::remove_file(&path);
+    }
+}
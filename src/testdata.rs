@@ -10,6 +10,8 @@ const ANON_GIFT_SUB: &str = r#"{"type":"MESSAGE","data":{"topic":"channel-subscr
 
 const GIFT_SUB: &str = r#"{"type":"MESSAGE","data":{"topic":"channel-subscribe-events-v1.474725923","message":"{\"benefit_end_month\":0,\"user_name\":\"temporus\",\"display_name\":\"Temporus\",\"channel_name\":\"togglebit\",\"user_id\":\"31515636\",\"channel_id\":\"474725923\",\"recipient_id\":\"86260954\",\"recipient_user_name\":\"bolvarsdad\",\"recipient_display_name\":\"Bolvarsdad\",\"time\":\"2021-09-30T09:10:40.349010399Z\",\"sub_message\":{\"message\":\"\",\"emotes\":null},\"sub_plan\":\"1000\",\"sub_plan_name\":\"Channel Subscription (togglebit)\",\"months\":1,\"context\":\"subgift\",\"is_gift\":true,\"multi_month_duration\":1}"}}"#;
 
+const COMMUNITY_GIFT: &str = r#"{"type":"MESSAGE","data":{"topic":"community-gift.474725923","message":"{\"display_name\":\"Temporus\",\"mass_gift_count\":5,\"sender_count\":42,\"sub_plan\":\"1000\"}"}}"#;
+
 const BITS: &str = r#"{"type":"MESSAGE","data":{"topic":"channel-bits-events-v2.474725923","message":"{\"data\":{\"user_name\":\"sir_klausi\",\"channel_name\":\"togglebit\",\"user_id\":\"209386371\",\"channel_id\":\"474725923\",\"time\":\"2021-09-30T09:13:06.377665427Z\",\"chat_message\":\"uni244\",\"bits_used\":244,\"total_bits_used\":2400,\"is_anonymous\":false,\"context\":\"cheer\",\"badge_entitlement\":null},\"version\":\"1.0\",\"message_type\":\"bits_event\",\"message_id\":\"2c834234-1401-5d94-8192-50b58c4cd56a\"}"}}"#;
 
 const OSLASH: &str = r##"{"type":"MESSAGE","data":{"topic":"channel-points-channel-v1.474725923","message":"{\"type\":\"reward-redeemed\",\"data\":{\"timestamp\":\"2021-10-20T11:42:00.561696412Z\",\"redemption\":{\"id\":\"283753eb-e9ea-47cd-a1ae-be0d20a62f57\",\"user\":{\"id\":\"474725923\",\"login\":\"togglebit\",\"display_name\":\"togglebit\"},\"channel_id\":\"474725923\",\"redeemed_at\":\"2021-10-20T11:42:00.561696412Z\",\"reward\":{\"id\":\"de138038-dc06-4f1e-a576-9b5e42bedb82\",\"channel_id\":\"474725923\",\"title\":\"Work on: Terminal Social Network\",\"prompt\":\"Spend an hour working on a terminal social network\",\"cost\":6000,\"is_user_input_required\":false,\"is_sub_only\":false,\"image\":{\"url_1x\":\"https://static-cdn.jtvnw.net/custom-reward-images/474725923/de138038-dc06-4f1e-a576-9b5e42bedb82/60f46769-5b3e-4961-8f2d-ec59d1924b25/custom-1.png\",\"url_2x\":\"https://static-cdn.jtvnw.net/custom-reward-images/474725923/de138038-dc06-4f1e-a576-9b5e42bedb82/60f46769-5b3e-4961-8f2d-ec59d1924b25/custom-2.png\",\"url_4x\":\"https://static-cdn.jtvnw.net/custom-reward-images/474725923/de138038-dc06-4f1e-a576-9b5e42bedb82/60f46769-5b3e-4961-8f2d-ec59d1924b25/custom-4.png\"},\"default_image\":{\"url_1x\":\"https://static-cdn.jtvnw.net/custom-reward-images/default-1.png\",\"url_2x\":\"https://static-cdn.jtvnw.net/custom-reward-images/default-2.png\",\"url_4x\":\"https://static-cdn.jtvnw.net/custom-reward-images/default-4.png\"},\"background_color\":\"#45415A\",\"is_enabled\":true,\"is_paused\":false,\"is_in_stock\":true,\"max_per_stream\":{\"is_enabled\":false,\"max_per_stream\":1},\"should_redemptions_skip_request_queue\":false,\"template_id\":null,\"updated_for_indicator_at\":\"2021-10-20T11:41:28.525050694Z\",\"max_per_user_per_stream\":{\"is_enabled\":false,\"max_per_user_per_stream\":0},\"global_cooldown\":{\"is_enabled\":false,\"global_cooldown_seconds\":0},\"redemptions_redeemed_current_stream\":null,\"cooldown_expires_at\":null},\"status\":\"UNFULFILLED\"}}}"}}"##;
@@ -42,6 +44,18 @@ pub async fn gift_sub() {
     send_twich_event(bytes).await;
 }
 
+pub async fn gift_bomb() {
+    send_twich_event(COMMUNITY_GIFT.as_bytes()).await;
+    // Every recipient is someone different
+    for i in 0..5 {
+        let gift = GIFT_SUB
+            .replace("86260954", &format!("8626095{}", i))
+            .replace("bolvarsdad", &format!("recipient{}", i))
+            .replace("Bolvarsdad", &format!("Recipient{}", i));
+        send_twich_event(gift.as_bytes()).await;
+    }
+}
+
 pub async fn sub() {
     let bytes = SUB.as_bytes();
     send_twich_event(bytes).await;
@@ -1,31 +1,232 @@
-use std::fs::{read_to_string, metadata};
-use std::path::Path;
-use std::time::{Duration, SystemTime};
+use std::fs::{read_to_string, rename, write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
 
 use crate::display::models::DisplayMessage;
 use crate::display::DisplayEventTx;
 
-pub async fn watch_todo(display_tx: DisplayEventTx, path: impl AsRef<Path>) -> Result<()> {
-    let mut current_ts = SystemTime::now();
-    loop {
-        let path = path.as_ref();
-        let meta = metadata(path)?;
-
-        if meta.modified()? != current_ts {
-            current_ts = meta.modified()?;
-            let data = match read_to_string(path) {
-                Ok(d) => d,
-                Err(e) => {
-                    log::error!("Failed to read file: {}", e);
-                    break;
-                }
+// Wait for writes to settle before reading the file
+const DEBOUNCE_MS: u64 = 300;
+
+// -----------------------------------------------------------------------------
+//     - Todo -
+//     The parts of markdown that matter for a todo list:
+//     headings and `* [ ]` / `* [x]` items. Everything else is plain text.
+// -----------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub enum TodoLine {
+    Heading(usize, String),
+    Item { done: bool, text: String },
+    Text(String),
+}
+
+impl TodoLine {
+    fn parse(line: &str) -> Option<Self> {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        if trimmed.starts_with('#') {
+            let level = trimmed.chars().take_while(|c| *c == '#').count();
+            return Some(TodoLine::Heading(level, trimmed[level..].trim().to_string()));
+        }
+
+        let item = trimmed.strip_prefix("* ").or_else(|| trimmed.strip_prefix("- "));
+        if let Some(item) = item {
+            let (done, text) = match item.get(..3) {
+                Some("[ ]") => (false, &item[3..]),
+                Some("[x]") | Some("[X]") => (true, &item[3..]),
+                _ => return Some(TodoLine::Text(trimmed.to_string())),
             };
-            display_tx.send(DisplayMessage::TodoUpdate(data))?;
+            return Some(TodoLine::Item { done, text: text.trim().to_string() });
+        }
+
+        Some(TodoLine::Text(trimmed.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Todo {
+    pub lines: Vec<TodoLine>,
+}
+
+impl Todo {
+    pub fn parse(src: &str) -> Self {
+        Self { lines: src.lines().filter_map(TodoLine::parse).collect() }
+    }
+
+    /// Unchecked items, numbered the same way as `!todo done <n>`
+    pub fn open_items(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                TodoLine::Item { done, text } => Some((*done, text.as_str())),
+                _ => None,
+            })
+            .enumerate()
+            .filter(|(_, (done, _))| !done)
+            .map(|(i, (_, text))| (i + 1, text))
+    }
+
+    /// Number of checked items and the total number of items
+    pub fn progress(&self) -> (usize, usize) {
+        self.lines.iter().fold((0, 0), |(done, total), line| match line {
+            TodoLine::Item { done: true, .. } => (done + 1, total + 1),
+            TodoLine::Item { .. } => (done, total + 1),
+            _ => (done, total),
+        })
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Editing -
+//     Edits only touch the lines they change, so the rest of
+//     the markdown is left exactly as the streamer wrote it.
+// -----------------------------------------------------------------------------
+fn add_line(src: &str, text: &str) -> String {
+    let mut src = src.to_string();
+    if !src.is_empty() && !src.ends_with('\n') {
+        src.push('\n');
+    }
+    src.push_str(&format!("* [ ] {}\n", text));
+    src
+}
+
+// Items are numbered from one, in the order they appear
+fn check_line(src: &str, number: usize) -> Result<(String, String)> {
+    let mut lines = src.split('\n').map(str::to_string).collect::<Vec<_>>();
+
+    let (index, text) = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| match TodoLine::parse(line) {
+            Some(TodoLine::Item { done, text }) => Some((i, done, text)),
+            _ => None,
+        })
+        .nth(number.wrapping_sub(1))
+        .map(|(i, done, text)| match done {
+            false => Ok((i, text)),
+            true => Err(anyhow!("{} is already done", number)),
+        })
+        .ok_or_else(|| anyhow!("There is no item {}", number))??;
+
+    lines[index] = lines[index].replacen("[ ]", "[x]", 1);
+    Ok((lines.join("\n"), text))
+}
+
+// Write to a temporary file next to the todo and rename it over the
+// old one, so the watcher never sees a half written file.
+fn write_atomic(path: &Path, data: &str) -> Result<()> {
+    let file_name = path.file_name().ok_or_else(|| anyhow!("{} is not a file", path.display()))?;
+    let tmp = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+    write(&tmp, data)?;
+    rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Add an unchecked item to the end of the todo
+pub fn add_item(path: &Path, text: &str) -> Result<()> {
+    let src = read_to_string(path)?;
+    write_atomic(path, &add_line(&src, text))
+}
+
+/// Check an item, returning its text
+pub fn complete_item(path: &Path, number: usize) -> Result<String> {
+    let src = read_to_string(path)?;
+    let (src, text) = check_line(&src, number)?;
+    write_atomic(path, &src)?;
+    Ok(text)
+}
+
+fn send_todo(display_tx: &DisplayEventTx, path: &Path) -> Result<()> {
+    let msg = match read_to_string(path) {
+        Ok(data) => DisplayMessage::TodoUpdate(data),
+        Err(e) => {
+            log::error!("Failed to read {}: {}", path.display(), e);
+            DisplayMessage::TodoUnavailable(e.to_string())
         }
+    };
+    display_tx.send(msg)?;
+    Ok(())
+}
+
+/// Watch the todo file and send every change to the display.
+///
+/// This watches the directory rather than the file itself, as editors
+/// tend to save by writing a new file and renaming it over the old one.
+/// Blocks until the display goes away.
+pub fn watch_todo(display_tx: DisplayEventTx, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let file_name = path.file_name().ok_or_else(|| anyhow!("{} is not a file", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if dir.as_os_str().is_empty() => Path::new("."),
+        Some(dir) => dir,
+        None => Path::new("/"),
+    };
+    let is_todo = |p: &PathBuf| p.file_name() == Some(file_name);
+
+    let (tx, rx) = channel();
+    let mut watcher = watcher(tx, Duration::from_millis(DEBOUNCE_MS))?;
+    if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+        display_tx.send(DisplayMessage::TodoUnavailable(format!("can't watch {}", dir.display())))?;
+        return Err(e.into());
+    }
+
+    send_todo(&display_tx, path)?;
 
-        tokio::time::sleep(Duration::from_secs(2)).await;
+    while let Ok(event) = rx.recv() {
+        match event {
+            DebouncedEvent::Create(p) | DebouncedEvent::Write(p) | DebouncedEvent::Rename(_, p) if is_todo(&p) => {
+                send_todo(&display_tx, path)?
+            }
+            DebouncedEvent::Remove(p) | DebouncedEvent::Rename(p, _) if is_todo(&p) => {
+                display_tx.send(DisplayMessage::TodoUnavailable("the file was removed".to_string()))?
+            }
+            DebouncedEvent::Rescan => send_todo(&display_tx, path)?,
+            DebouncedEvent::Error(e, _) => log::error!("Todo watcher: {}", e),
+            _ => {}
+        }
     }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_todo() {
+        let src = "# Stream\n\n* [ ] one\n* [x] two\n- [X] three\n* not an item\nsome text";
+        let todo = Todo::parse(src);
+        assert_eq!(todo.lines[0], TodoLine::Heading(1, "Stream".to_string()));
+        assert_eq!(todo.lines[1], TodoLine::Item { done: false, text: "one".to_string() });
+        assert_eq!(todo.lines[3], TodoLine::Item { done: true, text: "three".to_string() });
+        assert_eq!(todo.lines[4], TodoLine::Text("* not an item".to_string()));
+        assert_eq!(todo.progress(), (2, 3));
+    }
+
+    #[test]
+    fn check_items() {
+        let src = "# Stream\n* [x] one\n* [ ] two\n";
+        let (src, text) = check_line(src, 2).unwrap();
+        assert_eq!(src, "# Stream\n* [x] one\n* [x] two\n");
+        assert_eq!(text, "two");
+
+        assert!(check_line(&src, 1).is_err());
+        assert!(check_line(&src, 0).is_err());
+        assert!(check_line(&src, 3).is_err());
+    }
+
+    #[test]
+    fn add_items() {
+        assert_eq!(add_line("* [ ] one", "two"), "* [ ] one\n* [ ] two\n");
+        let todo = Todo::parse(&add_line("", "two"));
+        assert_eq!(todo.open_items().collect::<Vec<_>>(), vec![(1, "two")]);
+    }
+}
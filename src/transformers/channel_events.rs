@@ -1,32 +1,54 @@
+use std::collections::BTreeMap;
+
 use neotwitch::ChannelPoints;
 use fortune_countdown::Quotes;
 
+use crate::chat_out::ChatSender;
 use crate::display::models::{ChannelPointsMessage, DisplayMessage, ChatEvent};
 use crate::display::random_color;
 use crate::audio::{default_sound, random_arch};
+use crate::transformers::commands::Action;
 
 pub struct ChannelPointsTransformer {
     quotes: Quotes,
+    rewards: BTreeMap<String, Vec<Action>>,
+    chat: ChatSender,
 }
 
 impl ChannelPointsTransformer {
-    pub fn new() -> Self {
+    pub fn new(rewards: BTreeMap<String, Vec<Action>>, chat: ChatSender) -> Self {
         Self {
             quotes: Quotes::new("/home/togglebit/projects/rust/fortune-countdown/datfiles/").expect("Quotes missing"),
+            rewards,
+            chat,
         }
     }
 
-    pub fn transform(&mut self, chan_points: ChannelPoints) -> Option<DisplayMessage> {
+    pub fn transform(&mut self, chan_points: ChannelPoints) -> Vec<DisplayMessage> {
+        // Rewards from the config first
+        if let Some(actions) = self.rewards.get(&chan_points.reward.title) {
+            let user = chan_points.user.display_name.as_str();
+            let input = chan_points.user_input.as_deref().unwrap_or("");
+            let fill = |text: &str| text.replace("{user}", user).replace("{input}", input);
+            return actions.iter().filter_map(|action| action.run(user, &fill, &self.chat)).collect();
+        }
+
+        self.builtin(chan_points).into_iter().collect()
+    }
+
+    fn builtin(&mut self, chan_points: ChannelPoints) -> Option<DisplayMessage> {
         match chan_points.reward.title.as_ref() {
             "hydrate! (maybe)" => Some(DisplayMessage::ChannelPoints(ChannelPointsMessage {
                 user: chan_points.user.display_name,
                 title: chan_points.reward.title,
                 sound_path: Some(default_sound()),
+                user_input: chan_points.user_input,
             })),
             "what os are you using" => Some(DisplayMessage::ChannelPoints(ChannelPointsMessage {
                 user: chan_points.user.display_name,
                 title: chan_points.reward.title,
                 sound_path: Some(random_arch()),
+                user_input: chan_points.user_input,
             })),
             "random quote" => {
                 let color = random_color();
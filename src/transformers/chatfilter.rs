@@ -10,10 +10,15 @@ impl ChatFilter {
     }
 
     pub fn filter(&mut self, message: IrcMessage) -> Option<IrcMessage> {
-        if message.nick == "pretzelrocks" {
-            return None;
+        match self.allow(&message.nick) {
+            true => Some(message),
+            false => None,
         }
+    }
 
-        Some(message)
+    /// Check messages from outside of chat, like sub messages,
+    /// against the same rules as chat messages.
+    pub fn allow(&self, nick: &str) -> bool {
+        nick != "pretzelrocks"
     }
 }
@@ -0,0 +1,61 @@
+use serde::Deserialize;
+
+use crate::chat_out::ChatSender;
+use crate::display::models::{ChannelPointsMessage, ChatEvent, DisplayMessage};
+use crate::display::random_color;
+
+use super::{CommandOptions, Context, Handler};
+
+/// What a command or reward from the config does.
+/// For commands `{nick}` and `{args}` in the text are replaced
+/// with the user and whatever came after the command,
+/// for rewards `{user}` and `{input}` with the user and what they typed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum Action {
+    /// A banner in the chat pane
+    Banner { text: String },
+    /// A quote in the chat pane
+    Quote { text: String },
+    /// An alert in the event panel, with an optional sound
+    Alert { text: String, sound: Option<String> },
+    /// A message in Twitch chat
+    Say { text: String },
+}
+
+impl Action {
+    /// `fill` replaces the placeholders in the text
+    pub fn run(&self, user: &str, fill: &dyn Fn(&str) -> String, chat: &ChatSender) -> Option<DisplayMessage> {
+        match self {
+            Action::Banner { text } => Some(DisplayMessage::ChatEvent(ChatEvent(fill(text)))),
+            Action::Quote { text } => Some(DisplayMessage::Quote(fill(text), random_color())),
+            Action::Alert { text, sound } => Some(DisplayMessage::ChannelPoints(ChannelPointsMessage {
+                user: user.to_string(),
+                title: fill(text),
+                sound_path: sound.clone(),
+                user_input: None,
+            })),
+            Action::Say { text } => {
+                chat.say(fill(text));
+                None
+            }
+        }
+    }
+}
+
+/// A command defined in the config, e.g:
+/// "lurk": { "hidden": true, "actions": [{ "type": "banner", "text": "{nick} is lurking" }] }
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomCommand {
+    #[serde(flatten)]
+    pub options: CommandOptions,
+    pub actions: Vec<Action>,
+}
+
+impl Handler for CustomCommand {
+    fn handle(&mut self, ctx: &Context<'_>) -> Vec<DisplayMessage> {
+        let fill = |text: &str| text.replace("{nick}", ctx.nick).replace("{args}", ctx.args);
+
+        self.actions.iter().filter_map(|action| action.run(ctx.nick, &fill, ctx.chat)).collect()
+    }
+}
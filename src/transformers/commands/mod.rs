@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use neotwitch::IrcMessage;
+use serde::Deserialize;
+
+use crate::chat_out::ChatSender;
+use crate::config::Config;
+use crate::display::models::DisplayMessage;
+use crate::quotes::QuoteBook;
+use crate::transformers::poll::SharedPolls;
+use crate::transformers::shoutout::SharedShoutouts;
+
+mod custom;
+mod poll;
+mod quote;
+mod shoutout;
+mod todo;
+
+pub use custom::{Action, CustomCommand};
+use poll::{PollCommand, VoteCommand};
+use quote::QuoteCommand;
+use shoutout::ShoutoutCommand;
+use todo::TodoCommand;
+
+/// Who is allowed to run a command, lowest first.
+/// Anyone at or above the level can run it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    #[default]
+    Everyone,
+    Sub,
+    Vip,
+    Mod,
+    Broadcaster,
+}
+
+impl Permission {
+    /// The highest level the sender of a message has
+    pub fn of(irc: &IrcMessage) -> Self {
+        let badges = irc.tags.get("badges").map(String::as_str).unwrap_or("");
+        let has_badge = |name: &str| badges.split(',').any(|b| b.split('/').next() == Some(name));
+
+        if has_badge("broadcaster") {
+            Permission::Broadcaster
+        } else if has_badge("moderator") || irc.tags.get("mod").map(|m| m == "1").unwrap_or(false) {
+            Permission::Mod
+        } else if has_badge("vip") {
+            Permission::Vip
+        } else if has_badge("subscriber") || has_badge("founder") {
+            Permission::Sub
+        } else {
+            Permission::Everyone
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CommandOptions {
+    pub permission: Permission,
+    /// Seconds before anyone can use the command again
+    pub cooldown_secs: u64,
+    /// Seconds before the same user can use the command again
+    pub user_cooldown_secs: u64,
+    /// Keep the command out of the chat pane
+    pub hidden: bool,
+}
+
+/// Everything a handler knows about the message that ran the command
+pub struct Context<'a> {
+    pub nick: &'a str,
+    /// Everything after the command, trimmed
+    pub args: &'a str,
+    pub permission: Permission,
+    /// Reply in Twitch chat
+    pub chat: &'a ChatSender,
+}
+
+pub trait Handler: Send {
+    fn handle(&mut self, ctx: &Context<'_>) -> Vec<DisplayMessage>;
+}
+
+/// What came out of a command
+pub struct Reply {
+    pub messages: Vec<DisplayMessage>,
+    /// Don't show the message that ran the command in chat
+    pub hide: bool,
+}
+
+struct Registered {
+    options: CommandOptions,
+    handler: Box<dyn Handler>,
+    last_used: Option<Instant>,
+    last_used_by: HashMap<String, Instant>,
+}
+
+impl Registered {
+    // The broadcaster is never on cooldown
+    fn on_cooldown(&self, nick: &str, permission: Permission, now: Instant) -> bool {
+        if permission == Permission::Broadcaster {
+            return false;
+        }
+
+        let cooldown = Duration::from_secs(self.options.cooldown_secs);
+        let user_cooldown = Duration::from_secs(self.options.user_cooldown_secs);
+        let global = self.last_used.map(|t| now - t < cooldown).unwrap_or(false);
+        let user = self.last_used_by.get(nick).map(|t| now - *t < user_cooldown).unwrap_or(false);
+        global || user
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Command transformer -
+//     Messages starting with `!` are commands.
+//     Unknown commands are left alone and show up in chat as usual.
+// -----------------------------------------------------------------------------
+pub struct CommandTransformer {
+    commands: BTreeMap<String, Registered>,
+    chat: ChatSender,
+}
+
+impl CommandTransformer {
+    pub fn new(config: &Config, chat: ChatSender, polls: SharedPolls, shoutouts: SharedShoutouts) -> Self {
+        let mut inst = Self { commands: BTreeMap::new(), chat };
+
+        let todo_options = CommandOptions { permission: Permission::Mod, ..Default::default() };
+        inst.register("todo", todo_options, TodoCommand::new(config.todo_path.clone()));
+
+        let poll_options = CommandOptions { permission: Permission::Mod, ..Default::default() };
+        let duration = Duration::from_secs(config.polls.duration_secs);
+        inst.register("poll", poll_options, PollCommand::new(polls.clone(), duration));
+        // Votes would drown out the chat
+        let vote_options = CommandOptions { hidden: true, ..Default::default() };
+        inst.register("vote", vote_options, VoteCommand::new(polls));
+
+        let so_options = CommandOptions { permission: Permission::Mod, ..Default::default() };
+        inst.register("so", so_options, ShoutoutCommand::new(shoutouts));
+
+        // Adding quotes is for mods only, which the handler checks
+        match QuoteBook::load(&config.quotes_path) {
+            Ok(book) => inst.register("quote", CommandOptions::default(), QuoteCommand::new(book)),
+            Err(e) => log::error!("No !quote, failed to load quotes: {}", e),
+        }
+
+        for (name, command) in &config.commands {
+            inst.register(name, command.options.clone(), command.clone());
+        }
+
+        inst
+    }
+
+    /// Register a handler for `!<name>`, replacing any existing one
+    pub fn register(&mut self, name: &str, options: CommandOptions, handler: impl Handler + 'static) {
+        let registered =
+            Registered { options, handler: Box::new(handler), last_used: None, last_used_by: HashMap::new() };
+        self.commands.insert(name.to_lowercase(), registered);
+    }
+
+    pub fn transform(&mut self, irc: &IrcMessage) -> Option<Reply> {
+        self.run(&irc.nick, &irc.message, Permission::of(irc), Instant::now())
+    }
+
+    fn run(&mut self, nick: &str, message: &str, permission: Permission, now: Instant) -> Option<Reply> {
+        let message = message.trim().strip_prefix('!')?;
+        let mut words = message.splitn(2, ' ');
+        let name = words.next()?.to_lowercase();
+        let args = words.next().unwrap_or("").trim();
+
+        let command = self.commands.get_mut(&name)?;
+        let hide = command.options.hidden;
+
+        if permission < command.options.permission {
+            log::info!("{} is not allowed to use !{}", nick, name);
+            return Some(Reply { messages: vec![], hide });
+        }
+
+        if command.on_cooldown(nick, permission, now) {
+            return Some(Reply { messages: vec![], hide });
+        }
+
+        command.last_used = Some(now);
+        command.last_used_by.insert(nick.to_string(), now);
+
+        let ctx = Context { nick, args, permission, chat: &self.chat };
+        Some(Reply { messages: command.handler.handle(&ctx), hide })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Echo;
+
+    impl Handler for Echo {
+        fn handle(&mut self, ctx: &Context<'_>) -> Vec<DisplayMessage> {
+            vec![DisplayMessage::ChatEvent(crate::display::models::ChatEvent(ctx.args.to_string()))]
+        }
+    }
+
+    fn transformer(options: CommandOptions) -> CommandTransformer {
+        let (chat, _) = crate::chat_out::channel();
+        let mut transformer = CommandTransformer { commands: BTreeMap::new(), chat };
+        transformer.register("echo", options, Echo);
+        transformer
+    }
+
+    #[test]
+    fn not_a_command() {
+        let mut transformer = transformer(CommandOptions::default());
+        let now = Instant::now();
+        assert!(transformer.run("a", "echo hi", Permission::Everyone, now).is_none());
+        assert!(transformer.run("a", "!unknown hi", Permission::Everyone, now).is_none());
+    }
+
+    #[test]
+    fn permissions() {
+        let options = CommandOptions { permission: Permission::Vip, hidden: true, ..Default::default() };
+        let mut transformer = transformer(options);
+        let now = Instant::now();
+
+        let reply = transformer.run("a", "!echo hi", Permission::Sub, now).unwrap();
+        assert!(reply.messages.is_empty());
+        assert!(reply.hide);
+
+        let reply = transformer.run("a", "!ECHO hi", Permission::Mod, now).unwrap();
+        assert_eq!(reply.messages.len(), 1);
+    }
+
+    #[test]
+    fn cooldowns() {
+        let options = CommandOptions { cooldown_secs: 10, user_cooldown_secs: 60, ..Default::default() };
+        let mut transformer = transformer(options);
+        let now = Instant::now();
+        let run = |t: &mut CommandTransformer, nick: &str, secs: u64| {
+            t.run(nick, "!echo", Permission::Everyone, now + Duration::from_secs(secs)).unwrap().messages.len()
+        };
+
+        assert_eq!(run(&mut transformer, "a", 0), 1);
+        // Global cooldown
+        assert_eq!(run(&mut transformer, "b", 5), 0);
+        assert_eq!(run(&mut transformer, "b", 11), 1);
+        // User cooldown
+        assert_eq!(run(&mut transformer, "a", 30), 0);
+        assert_eq!(run(&mut transformer, "a", 61), 1);
+        // Except for the broadcaster
+        let reply = transformer.run("c", "!echo", Permission::Broadcaster, now + Duration::from_secs(62)).unwrap();
+        assert_eq!(reply.messages.len(), 1);
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+
+use crate::display::models::{ChatEvent, DisplayMessage};
+use crate::transformers::poll::SharedPolls;
+
+use super::{Context, Handler};
+
+/// `!poll <title> | <choice> | <choice> ...` and `!poll end`
+pub struct PollCommand {
+    polls: SharedPolls,
+    duration: Duration,
+}
+
+impl PollCommand {
+    pub fn new(polls: SharedPolls, duration: Duration) -> Self {
+        Self { polls, duration }
+    }
+}
+
+impl Handler for PollCommand {
+    fn handle(&mut self, ctx: &Context<'_>) -> Vec<DisplayMessage> {
+        let mut polls = self.polls.lock().expect("the poll is rigged");
+
+        if ctx.args == "end" {
+            return polls.end().map(DisplayMessage::Poll).into_iter().collect();
+        }
+
+        let mut parts = ctx.args.split('|').map(str::trim).filter(|p| !p.is_empty());
+        let title = parts.next().unwrap_or("");
+        let choices = parts.map(str::to_string).collect::<Vec<_>>();
+
+        match polls.start(title, choices, self.duration, Instant::now()) {
+            Some(poll) => {
+                let choices = poll.choices.iter().enumerate().map(|(i, c)| format!("{} = {}", i + 1, c.title));
+                ctx.chat.say(format!("Poll: {} Vote with !vote {}", title, choices.collect::<Vec<_>>().join(", ")));
+                vec![DisplayMessage::Poll(poll)]
+            }
+            None => vec![DisplayMessage::ChatEvent(ChatEvent(
+                "Usage: !poll <title> | <choice> | <choice> ... (one poll at a time)".to_string(),
+            ))],
+        }
+    }
+}
+
+/// `!vote <number>`
+pub struct VoteCommand {
+    polls: SharedPolls,
+}
+
+impl VoteCommand {
+    pub fn new(polls: SharedPolls) -> Self {
+        Self { polls }
+    }
+}
+
+impl Handler for VoteCommand {
+    fn handle(&mut self, ctx: &Context<'_>) -> Vec<DisplayMessage> {
+        let choice = match ctx.args.trim_start_matches('#').parse::<usize>() {
+            Ok(choice) => choice,
+            Err(_) => return vec![],
+        };
+
+        let mut polls = self.polls.lock().expect("the poll is rigged");
+        polls.vote(ctx.nick, choice).map(DisplayMessage::Poll).into_iter().collect()
+    }
+}
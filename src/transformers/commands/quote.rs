@@ -0,0 +1,79 @@
+use crate::display::models::{ChatEvent, DisplayMessage};
+use crate::display::random_color;
+use crate::quotes::{Quote, QuoteBook};
+
+use super::{Context, Handler, Permission};
+
+const USAGE: &str = "Usage: !quote [id] | !quote search <text> | !quote add <text> [| game]";
+
+/// `!quote` for a random quote, `!quote <id>`, `!quote search <text>`
+/// and, for mods, `!quote add <text> [| game]`.
+pub struct QuoteCommand {
+    book: QuoteBook,
+}
+
+impl QuoteCommand {
+    pub fn new(book: QuoteBook) -> Self {
+        Self { book }
+    }
+
+    fn add(&mut self, ctx: &Context<'_>, rest: &str) -> DisplayMessage {
+        if ctx.permission < Permission::Mod {
+            return DisplayMessage::ChatEvent(ChatEvent("Only mods can add quotes".to_string()));
+        }
+
+        let mut parts = rest.splitn(2, '|');
+        let text = parts.next().unwrap_or("").trim().trim_matches('"');
+        let game = parts.next().map(str::trim).filter(|g| !g.is_empty());
+        if text.trim().is_empty() {
+            return DisplayMessage::ChatEvent(ChatEvent(USAGE.to_string()));
+        }
+
+        let message = match self.book.add(text, ctx.nick, game) {
+            Ok(quote) => format!("Quote #{} added", quote.id),
+            Err(e) => {
+                log::error!("!quote add: {}", e);
+                format!("Quote failed: {}", e)
+            }
+        };
+        DisplayMessage::ChatEvent(ChatEvent(message))
+    }
+}
+
+// Show the quote on screen and say it in chat
+fn show(ctx: &Context<'_>, quote: &Quote) -> DisplayMessage {
+    let text = quote.display();
+    ctx.chat.say(&text);
+    DisplayMessage::Quote(text, random_color())
+}
+
+impl Handler for QuoteCommand {
+    fn handle(&mut self, ctx: &Context<'_>) -> Vec<DisplayMessage> {
+        let mut args = ctx.args.splitn(2, ' ');
+        let sub_command = args.next().unwrap_or("");
+        let rest = args.next().unwrap_or("").trim();
+
+        let message = match sub_command {
+            "add" if !rest.is_empty() => self.add(ctx, rest),
+            "search" if !rest.is_empty() => match self.book.search(rest).as_slice() {
+                [] => DisplayMessage::ChatEvent(ChatEvent(format!("No quotes matching \"{}\"", rest))),
+                [quote] => show(ctx, quote),
+                [quote, others @ ..] => {
+                    let ids = others.iter().map(|q| format!("#{}", q.id)).collect::<Vec<_>>();
+                    ctx.chat.say(format!("Also matching: {}", ids.join(", ")));
+                    show(ctx, quote)
+                }
+            },
+            "" => match self.book.random() {
+                Some(quote) => show(ctx, quote),
+                None => DisplayMessage::ChatEvent(ChatEvent("No quotes yet".to_string())),
+            },
+            id => match id.trim_start_matches('#').parse::<usize>().ok().and_then(|id| self.book.get(id)) {
+                Some(quote) => show(ctx, quote),
+                None => DisplayMessage::ChatEvent(ChatEvent(USAGE.to_string())),
+            },
+        };
+
+        vec![message]
+    }
+}
@@ -0,0 +1,28 @@
+use crate::display::models::{ChatEvent, DisplayMessage};
+use crate::transformers::shoutout::SharedShoutouts;
+
+use super::{Context, Handler};
+
+/// `!so <user>`
+pub struct ShoutoutCommand {
+    shoutouts: SharedShoutouts,
+}
+
+impl ShoutoutCommand {
+    pub fn new(shoutouts: SharedShoutouts) -> Self {
+        Self { shoutouts }
+    }
+}
+
+impl Handler for ShoutoutCommand {
+    fn handle(&mut self, ctx: &Context<'_>) -> Vec<DisplayMessage> {
+        let user = ctx.args.split_whitespace().next().unwrap_or("").trim_start_matches('@');
+        if user.is_empty() {
+            return vec![DisplayMessage::ChatEvent(ChatEvent("Usage: !so <user>".to_string()))];
+        }
+
+        let message = self.shoutouts.lock().expect("nobody to shout at").shoutout(user);
+        ctx.chat.say(&message);
+        vec![DisplayMessage::ChatEvent(ChatEvent(message))]
+    }
+}
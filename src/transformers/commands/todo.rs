@@ -0,0 +1,59 @@
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use crate::display::models::{ChatEvent, DisplayMessage};
+use crate::todo::{self, Todo};
+
+use super::{Context, Handler};
+
+// Only show this many open items for `!todo list`
+const LIST_MAX: usize = 5;
+
+/// `!todo add <text>`, `!todo done <number>` and `!todo list`.
+/// The watcher picks up the change to the file,
+/// so the todo pane updates on its own.
+pub struct TodoCommand {
+    todo_path: PathBuf,
+}
+
+impl TodoCommand {
+    pub fn new(todo_path: PathBuf) -> Self {
+        Self { todo_path }
+    }
+}
+
+impl Handler for TodoCommand {
+    fn handle(&mut self, ctx: &Context<'_>) -> Vec<DisplayMessage> {
+        let mut args = ctx.args.splitn(2, ' ');
+        let sub_command = args.next().unwrap_or("");
+        let rest = args.next().unwrap_or("").trim();
+
+        let result = match sub_command {
+            "add" if !rest.is_empty() => {
+                todo::add_item(&self.todo_path, rest).map(|()| format!("Todo added: {}", rest))
+            }
+            "done" => match rest.parse::<usize>() {
+                Ok(number) => todo::complete_item(&self.todo_path, number).map(|text| format!("Todo done: {}", text)),
+                Err(_) => Ok("Usage: !todo done <number>".to_string()),
+            },
+            "list" => read_to_string(&self.todo_path).map_err(Into::into).map(|src| list(&Todo::parse(&src))),
+            _ => Ok("Usage: !todo add <text> | !todo done <number> | !todo list".to_string()),
+        };
+
+        let message = result.unwrap_or_else(|e| {
+            log::error!("!todo {}: {}", sub_command, e);
+            format!("Todo failed: {}", e)
+        });
+
+        vec![DisplayMessage::ChatEvent(ChatEvent(message))]
+    }
+}
+
+fn list(todo: &Todo) -> String {
+    let (done, total) = todo.progress();
+    let items = todo.open_items().take(LIST_MAX).map(|(n, text)| format!("{}. {}", n, text)).collect::<Vec<_>>();
+    match items.is_empty() {
+        true => format!("Todo {}/{}: all done!", done, total),
+        false => format!("Todo {}/{}: {}", done, total, items.join(", ")),
+    }
+}
@@ -1,29 +1,120 @@
+use std::collections::{HashMap, VecDeque};
 use std::time::{Instant, Duration};
 
 use neotwitch::FollowEvent;
 
-use crate::display::models::Follow;
+use crate::config::FollowConfig;
+use crate::display::models::{ChatEvent, DisplayMessage, Follow};
 
 const FOLLOW_DRAIN_LIFE_SECS: u64 = 2;
 
+/// Why a follow didn't get an alert
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Suppressed {
+    Burst,
+    Duplicate,
+    RaidMode,
+}
+
+impl Suppressed {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Suppressed::Burst => "burst",
+            Suppressed::Duplicate => "duplicate",
+            Suppressed::RaidMode => "raid mode",
+        }
+    }
+}
+
 pub struct FollowTransformer {
+    config: FollowConfig,
     last_drain: Instant,
-    follows: Vec<Follow>
+    follows: Vec<Follow>,
+    // Time of every follow within the burst window
+    recent: VecDeque<Instant>,
+    seen: HashMap<String, Instant>,
+    suppressed: Vec<(String, Suppressed)>,
+    bursting: bool,
+    raid_mode: bool,
 }
 
 impl FollowTransformer {
-    pub fn new() -> Self {
+    pub fn new(config: FollowConfig) -> Self {
         Self {
+            config,
             last_drain: Instant::now(),
             follows: Vec::new(),
+            recent: VecDeque::new(),
+            seen: HashMap::new(),
+            suppressed: Vec::new(),
+            bursting: false,
+            raid_mode: false,
+        }
+    }
+
+    /// Returns a chat event when a follow burst starts
+    pub fn transform(&mut self, follow: FollowEvent) -> Option<DisplayMessage> {
+        let now = Instant::now();
+        let name = follow.display_name;
+
+        let dedupe = Duration::from_secs(self.config.dedupe_secs);
+        if self.seen.get(&name).map(|seen| now - *seen < dedupe).unwrap_or(false) {
+            self.suppressed.push((name, Suppressed::Duplicate));
+            return None;
+        }
+        self.seen.insert(name.clone(), now);
+
+        self.recent.push_back(now);
+        self.prune(now);
+
+        if self.recent.len() >= self.config.burst_count {
+            // The follows waiting to be drained are part of the burst as well
+            let queued = self.follows.drain(..).map(|f| (f.0, Suppressed::Burst));
+            self.suppressed.extend(queued);
+            self.suppressed.push((name, Suppressed::Burst));
+
+            if !self.bursting {
+                self.bursting = true;
+                let event = ChatEvent("Follow burst! Holding back follow alerts".to_string());
+                return Some(DisplayMessage::ChatEvent(event));
+            }
+            return None;
+        }
+
+        match self.raid_mode {
+            true => self.suppressed.push((name, Suppressed::RaidMode)),
+            false => self.follows.push(Follow(name)),
         }
+
+        None
     }
 
-    pub fn transform(&mut self, follow: FollowEvent) {
-        self.follows.push(Follow(follow.display_name));
+    pub fn set_raid_mode(&mut self, raid_mode: bool) {
+        self.raid_mode = raid_mode;
+    }
+
+    /// Everyone who followed without getting an alert since the last call
+    pub fn suppressed(&mut self) -> Vec<(String, Suppressed)> {
+        self.suppressed.drain(..).collect()
+    }
+
+    fn prune(&mut self, now: Instant) {
+        let window = Duration::from_secs(self.config.burst_secs);
+        while self.recent.front().map(|t| now - *t > window).unwrap_or(false) {
+            self.recent.pop_front();
+        }
+
+        if self.recent.len() < self.config.burst_count {
+            self.bursting = false;
+        }
     }
 
     pub fn outstanding(&mut self) -> Option<Vec<Follow>> {
+        let now = Instant::now();
+        self.prune(now);
+        let dedupe = Duration::from_secs(self.config.dedupe_secs);
+        self.seen.retain(|_, seen| now - *seen < dedupe);
+
         if self.follows.is_empty() {
             return None;
         }
@@ -36,3 +127,46 @@ impl FollowTransformer {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn follow(name: &str) -> FollowEvent {
+        let json = format!(r#"{{"display_name":"{0}","username":"{0}","user_id":"1"}}"#, name);
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn config() -> FollowConfig {
+        FollowConfig { burst_count: 3, burst_secs: 10, dedupe_secs: 60 }
+    }
+
+    #[test]
+    fn dedupe_follows() {
+        let mut transformer = FollowTransformer::new(config());
+        transformer.transform(follow("a"));
+        transformer.transform(follow("a"));
+        assert_eq!(transformer.follows.len(), 1);
+        assert_eq!(transformer.suppressed(), vec![("a".to_string(), Suppressed::Duplicate)]);
+    }
+
+    #[test]
+    fn burst_suppresses_queued_follows() {
+        let mut transformer = FollowTransformer::new(config());
+        assert!(transformer.transform(follow("a")).is_none());
+        assert!(transformer.transform(follow("b")).is_none());
+        assert!(transformer.transform(follow("c")).is_some());
+        assert!(transformer.transform(follow("d")).is_none());
+        assert!(transformer.follows.is_empty());
+        assert_eq!(transformer.suppressed().len(), 4);
+    }
+
+    #[test]
+    fn raid_mode() {
+        let mut transformer = FollowTransformer::new(config());
+        transformer.set_raid_mode(true);
+        transformer.transform(follow("a"));
+        assert!(transformer.follows.is_empty());
+        assert_eq!(transformer.suppressed(), vec![("a".to_string(), Suppressed::RaidMode)]);
+    }
+}
@@ -21,32 +21,55 @@
 //                       |  /            /   |/\/
 //                        ~~             /  /
 //                                      |__/
-use std::time::Duration;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use tokio::time;
 
-use crate::display::models::DisplayMessage;
+use crate::chat_out::ChatSender;
+use crate::config::Config;
+use crate::control::Control;
+use crate::display::models::{Bits, ChatEvent, DisplayMessage, Highlight, Raid};
 use crate::display::DisplayEventTx;
+use crate::redemptions::{Redemption, Status};
+use crate::stats::Stats;
 use crate::{Event, EventReceiver};
-use crate::audio::{random_sub, random_follow};
+use crate::audio::{default_sound, random_sub, random_follow};
 
 mod channel_events;
 mod chat;
 mod chatfilter;
+pub mod commands;
 mod filters;
 mod sub;
 mod follow;
+pub mod poll;
+pub mod shoutout;
+mod tts;
+mod viewer;
 
 use channel_events::ChannelPointsTransformer;
 use chat::IrcTransformer;
 use chatfilter::ChatFilter;
+use commands::CommandTransformer;
 use filters::Filters;
 use sub::SubTransformer;
-use follow::FollowTransformer;
+use follow::{FollowTransformer, Suppressed};
+use poll::{Polls, SharedPolls};
+use shoutout::{SharedShoutouts, Shoutouts};
+use tts::TtsTransformer;
+use viewer::ViewerTransformer;
 
-pub async fn run(mut event_rx: EventReceiver, display_tx: DisplayEventTx) {
-    let mut transformers = Transformers::new();
+pub async fn run(
+    mut event_rx: EventReceiver,
+    display_tx: DisplayEventTx,
+    chat_tx: ChatSender,
+    stats_path: PathBuf,
+    config: Config,
+) {
+    let mut transformers = Transformers::new(&config, chat_tx.clone());
     let mut filters = Filters::new();
+    let mut stats = Stats::new();
 
     // Receive an event.
     // Queue the display event for sending,
@@ -57,25 +80,97 @@ pub async fn run(mut event_rx: EventReceiver, display_tx: DisplayEventTx) {
         tokio::select! {
             () = time::sleep(Duration::from_secs(1)) => {
                 // Drain subs
-                for sub in transformers.subs.outstanding() {
+                for mut sub in transformers.subs.outstanding() {
+                    stats.sub(&sub);
+                    transformers.viewers.sub(&sub);
+                    let name = sub.display_name.as_deref().unwrap_or("");
+                    if !filters.chat_filter.allow(name) {
+                        sub.message.clear();
+                    }
                     if let Err(e) = display_tx.send(DisplayMessage::Sub(sub, random_sub())) {
                         log::error!("Failed to send sub to the display: {}", e);
                     }
                 }
+                // Gift bombs are shown as they happen, so they only count towards the stats
+                for sub in transformers.subs.finished_bombs() {
+                    stats.sub(&sub);
+                    transformers.viewers.sub(&sub);
+                }
                 if let Some(follows) = transformers.follow.outstanding() {
+                    follows.iter().for_each(|follow| stats.follow(&follow.0));
+                    follows.iter().for_each(|follow| transformers.viewers.follow(&follow.0));
+                    {
+                        let mut shoutouts = transformers.shoutouts.lock().expect("nobody to shout at");
+                        follows.iter().for_each(|follow| shoutouts.follow(&follow.0));
+                    }
+                    if let Some(thanks) = &config.chat_out.follow_thanks {
+                        let names = follows.iter().map(|f| f.0.as_str()).collect::<Vec<_>>().join(", ");
+                        chat_tx.say(thanks.replace("{names}", &names));
+                    }
                     if let Err(e) = display_tx.send(DisplayMessage::Follow(follows, random_follow())) {
                         log::error!("Failed to send follows to the display: {}", e);
                     }
                 }
+                // Suppressed follows still count as followers, only the alert was skipped
+                for (name, reason) in transformers.follow.suppressed() {
+                    if reason != Suppressed::Duplicate {
+                        stats.follow(&name);
+                    }
+                    stats.suppressed_follow(&name, reason.as_str());
+                }
+                let finished_poll = transformers.polls.lock().expect("the poll is rigged").expire(Instant::now());
+                if let Some(poll) = finished_poll {
+                    if let Err(e) = display_tx.send(DisplayMessage::Poll(poll)) {
+                        log::error!("Failed to send poll result to the display: {}", e);
+                    }
+                }
+
+                if let Err(e) = stats.save(&stats_path) {
+                    log::error!("Failed to save stats: {}", e);
+                }
             }
             event = event_rx.recv() => {
                 if let Some(event) = event {
                     match event {
                         Event::Chat(irc) => {
                             if let Some(irc) = filters.chat_filter.filter(irc) {
-                                let message = transformers.chat.transform(irc);
-                                if let Err(e) = display_tx.send(message) {
-                                    log::error!("Failed to send message to the display: {}", e);
+                                stats.chat(&irc.nick);
+                                let (highlight, greeting) = transformers.viewers.chat(&irc.nick);
+                                if highlight == Some(Highlight::FirstTime) {
+                                    stats.first_chat(&irc.nick);
+                                }
+                                if let Some(greeting) = greeting {
+                                    if config.viewers.greet_in_chat {
+                                        chat_tx.say(&greeting);
+                                    }
+                                    if let Err(e) = display_tx.send(DisplayMessage::ChatEvent(ChatEvent(greeting))) {
+                                        log::error!("Failed to send greeting to the display: {}", e);
+                                    }
+                                }
+                                let prompt = transformers.shoutouts.lock().expect("nobody to shout at").chat(&irc.nick);
+                                if let Some(prompt) = prompt {
+                                    if let Err(e) = display_tx.send(prompt) {
+                                        log::error!("Failed to send shoutout prompt to the display: {}", e);
+                                    }
+                                }
+                                let mut hide = false;
+                                if let Some(reply) = transformers.commands.transform(&irc) {
+                                    hide = reply.hide;
+                                    for message in reply.messages {
+                                        if let Err(e) = display_tx.send(message) {
+                                            log::error!("Failed to send command reply to the display: {}", e);
+                                        }
+                                    }
+                                }
+
+                                if !hide {
+                                    let mut message = transformers.chat.transform(irc);
+                                    if let DisplayMessage::Chat(chat) = &mut message {
+                                        chat.highlight = highlight;
+                                    }
+                                    if let Err(e) = display_tx.send(message) {
+                                        log::error!("Failed to send message to the display: {}", e);
+                                    }
                                 }
                             }
                         }
@@ -86,16 +181,126 @@ pub async fn run(mut event_rx: EventReceiver, display_tx: DisplayEventTx) {
                         }
                         Event::Twitch(twitch) => {
                             match twitch {
-                                crate::twitch::Twitch::ChannelEvent(channel_event) => {
-                                    if let Some(message) = transformers.channel_events.transform(channel_event) {
+                                crate::twitch::Twitch::ChannelEvent(mut channel_event) => {
+                                    stats.redemption(&channel_event.reward.title);
+                                    // What the viewer typed goes by the same rules as chat
+                                    if !filters.chat_filter.allow(&channel_event.user.display_name) {
+                                        channel_event.user_input = None;
+                                    }
+                                    let redemption = Redemption {
+                                        id: channel_event.id.clone(),
+                                        reward_id: channel_event.reward.id.clone(),
+                                        user: channel_event.user.display_name.clone(),
+                                        title: channel_event.reward.title.clone(),
+                                        user_input: channel_event.user_input.clone(),
+                                        redeemed: chrono::Utc::now().timestamp(),
+                                        status: Status::Unfulfilled,
+                                    };
+                                    if let Err(e) = display_tx.send(DisplayMessage::Redemption(redemption)) {
+                                        log::error!("Failed to send redemption to the display: {}", e);
+                                    }
+                                    let title = &channel_event.reward.title;
+                                    let user_input = channel_event.user_input.as_deref();
+                                    if let Some(speech) = transformers.tts.redemption(title, user_input) {
+                                        if let Err(e) = display_tx.send(speech) {
+                                            log::error!("Failed to send speech to the display: {}", e);
+                                        }
+                                    }
+                                    for message in transformers.channel_events.transform(channel_event) {
+                                        if let Err(e) = display_tx.send(message) {
+                                            log::error!("Failed to send message to the display: {}", e);
+                                        }
+                                    }
+                                }
+                                crate::twitch::Twitch::Sub(sub) => {
+                                    if let Some(message) = transformers.subs.transform(sub) {
+                                        if let Err(e) = display_tx.send(message) {
+                                            log::error!("Failed to send gift recipient to the display: {}", e);
+                                        }
+                                    }
+                                }
+                                crate::twitch::Twitch::CommunityGift(gift) => {
+                                    let message = transformers.subs.community_gift(gift, random_sub());
+                                    if let Err(e) = display_tx.send(message) {
+                                        log::error!("Failed to send gift bomb to the display: {}", e);
+                                    }
+                                }
+                                crate::twitch::Twitch::Follow(follow) => {
+                                    if let Some(message) = transformers.follow.transform(follow) {
                                         if let Err(e) = display_tx.send(message) {
                                             log::error!("Failed to send message to the display: {}", e);
                                         }
                                     }
                                 }
-                                crate::twitch::Twitch::Sub(sub) => transformers.subs.transform(sub),
-                                crate::twitch::Twitch::Follow(follow) => transformers.follow.transform(follow),
-                                _ => { log::info!("Not implemented:\n{:?}", twitch); }
+                                crate::twitch::Twitch::Bits(bits) => {
+                                    stats.bits(&bits.data.user_name, bits.data.bits_used as usize);
+                                    if filters.chat_filter.allow(&bits.data.user_name) {
+                                        let speech = transformers.tts.bits(
+                                            &bits.data.user_name,
+                                            bits.data.bits_used as usize,
+                                            &bits.data.chat_message,
+                                        );
+                                        if let Some(speech) = speech {
+                                            if let Err(e) = display_tx.send(speech) {
+                                                log::error!("Failed to send speech to the display: {}", e);
+                                            }
+                                        }
+                                    }
+                                    let bits = Bits { name: bits.data.user_name, amount: bits.data.bits_used as usize };
+                                    if let Err(e) = display_tx.send(DisplayMessage::Bits(bits, default_sound())) {
+                                        log::error!("Failed to send bits to the display: {}", e);
+                                    }
+                                }
+                                crate::twitch::Twitch::Poll(p) => {
+                                    if let Err(e) = display_tx.send(DisplayMessage::Poll(poll::from_twitch(p))) {
+                                        log::error!("Failed to send poll to the display: {}", e);
+                                    }
+                                }
+                                crate::twitch::Twitch::Prediction(p) => {
+                                    if let Err(e) = display_tx.send(DisplayMessage::Poll(poll::from_prediction(p))) {
+                                        log::error!("Failed to send prediction to the display: {}", e);
+                                    }
+                                }
+                                crate::twitch::Twitch::Raid(raid) => {
+                                    stats.raid(&raid.display_name, raid.viewer_count);
+                                    let prompt = {
+                                        let mut shoutouts = transformers.shoutouts.lock().expect("nobody to shout at");
+                                        shoutouts.raid(&raid.display_name)
+                                    };
+                                    if let Err(e) = display_tx.send(prompt) {
+                                        log::error!("Failed to send shoutout prompt to the display: {}", e);
+                                    }
+                                    if let Some(welcome) = &config.chat_out.raid_welcome {
+                                        let welcome = welcome
+                                            .replace("{name}", &raid.display_name)
+                                            .replace("{viewers}", &raid.viewer_count.to_string());
+                                        chat_tx.say(welcome);
+                                    }
+                                    let raid = Raid { name: raid.display_name, viewers: raid.viewer_count };
+                                    if let Err(e) = display_tx.send(DisplayMessage::Raid(raid, random_sub())) {
+                                        log::error!("Failed to send raid to the display: {}", e);
+                                    }
+                                }
+                            }
+                        }
+                        Event::Control(Control::Credits) => {
+                            if let Err(e) = display_tx.send(DisplayMessage::Credits(stats.clone())) {
+                                log::error!("Failed to send credits to the display: {}", e);
+                            }
+                        }
+                        Event::Control(Control::Layout(name)) => {
+                            if let Err(e) = display_tx.send(DisplayMessage::Layout(name)) {
+                                log::error!("Failed to send layout to the display: {}", e);
+                            }
+                        }
+                        Event::Control(Control::RaidMode(raid_mode)) => {
+                            transformers.follow.set_raid_mode(raid_mode);
+                            let message = match raid_mode {
+                                true => "Raid mode on, follow alerts are paused",
+                                false => "Raid mode off",
+                            };
+                            if let Err(e) = display_tx.send(DisplayMessage::ChatEvent(ChatEvent(message.to_string()))) {
+                                log::error!("Failed to send message to the display: {}", e);
                             }
                         }
                         _ => {}
@@ -108,18 +313,30 @@ pub async fn run(mut event_rx: EventReceiver, display_tx: DisplayEventTx) {
 
 pub struct Transformers {
     chat: IrcTransformer,
+    commands: CommandTransformer,
     channel_events: ChannelPointsTransformer,
     subs: SubTransformer,
     follow: FollowTransformer,
+    polls: SharedPolls,
+    tts: TtsTransformer,
+    shoutouts: SharedShoutouts,
+    viewers: ViewerTransformer,
 }
 
 impl Transformers {
-    fn new() -> Self {
+    fn new(config: &Config, chat_tx: ChatSender) -> Self {
+        let polls = Polls::shared();
+        let shoutouts = Shoutouts::shared(&config.shoutouts);
         Self {
             chat: IrcTransformer::new(),
-            channel_events: ChannelPointsTransformer::new(),
+            commands: CommandTransformer::new(config, chat_tx.clone(), polls.clone(), shoutouts.clone()),
+            channel_events: ChannelPointsTransformer::new(config.rewards.clone(), chat_tx),
             subs: SubTransformer::new(),
-            follow: FollowTransformer::new(),
+            follow: FollowTransformer::new(config.follows.clone()),
+            polls,
+            tts: TtsTransformer::new(config.tts.clone()),
+            shoutouts,
+            viewers: ViewerTransformer::new(&config.viewers),
         }
     }
 }
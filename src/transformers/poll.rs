@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::display::models::{Poll, PollChoice};
+use crate::twitch::{Prediction, TwitchPoll};
+
+/// Shared between `!poll`, `!vote` and the tick that ends the poll
+pub type SharedPolls = Arc<Mutex<Polls>>;
+
+struct LocalPoll {
+    title: String,
+    choices: Vec<String>,
+    // Nick -> choice index, so changing a vote is allowed
+    votes: HashMap<String, usize>,
+    ends: Instant,
+}
+
+impl LocalPoll {
+    fn poll(&self, finished: bool) -> Poll {
+        let choices = self
+            .choices
+            .iter()
+            .enumerate()
+            .map(|(i, title)| PollChoice {
+                title: title.clone(),
+                votes: self.votes.values().filter(|v| **v == i).count(),
+            })
+            .collect();
+        Poll { title: self.title.clone(), choices, finished, winner: None }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Local polls -
+//     One poll at a time, voted on with `!vote <number>`.
+// -----------------------------------------------------------------------------
+#[derive(Default)]
+pub struct Polls {
+    local: Option<LocalPoll>,
+}
+
+impl Polls {
+    pub fn shared() -> SharedPolls {
+        Arc::new(Mutex::new(Self::default()))
+    }
+
+    pub fn start(&mut self, title: &str, choices: Vec<String>, duration: Duration, now: Instant) -> Option<Poll> {
+        if self.local.is_some() || choices.len() < 2 {
+            return None;
+        }
+
+        let poll = LocalPoll { title: title.to_string(), choices, votes: HashMap::new(), ends: now + duration };
+        let snapshot = poll.poll(false);
+        self.local = Some(poll);
+        Some(snapshot)
+    }
+
+    /// Vote for a choice, counting from 1
+    pub fn vote(&mut self, nick: &str, choice: usize) -> Option<Poll> {
+        let poll = self.local.as_mut()?;
+        if choice == 0 || choice > poll.choices.len() {
+            return None;
+        }
+        poll.votes.insert(nick.to_string(), choice - 1);
+        Some(poll.poll(false))
+    }
+
+    pub fn end(&mut self) -> Option<Poll> {
+        self.local.take().map(|poll| poll.poll(true))
+    }
+
+    /// End the poll if time is up
+    pub fn expire(&mut self, now: Instant) -> Option<Poll> {
+        match &self.local {
+            Some(poll) if poll.ends <= now => self.end(),
+            _ => None,
+        }
+    }
+}
+
+pub fn from_twitch(poll: TwitchPoll) -> Poll {
+    Poll {
+        title: poll.title,
+        choices: poll.choices.into_iter().map(|c| PollChoice { title: c.title, votes: c.votes }).collect(),
+        finished: poll.status != "active",
+        winner: None,
+    }
+}
+
+pub fn from_prediction(prediction: Prediction) -> Poll {
+    let winner = prediction
+        .winning_outcome_id
+        .as_ref()
+        .and_then(|id| prediction.outcomes.iter().position(|o| &o.id == id));
+
+    Poll {
+        title: prediction.title,
+        choices: prediction
+            .outcomes
+            .into_iter()
+            .map(|o| PollChoice { title: o.title, votes: o.channel_points })
+            .collect(),
+        finished: prediction.status == "resolved" || prediction.status == "canceled",
+        winner,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn local_poll() {
+        let mut polls = Polls::default();
+        let now = Instant::now();
+        let choices = vec!["rust".to_string(), "also rust".to_string()];
+
+        assert!(polls.vote("a", 1).is_none());
+        assert!(polls.start("lang?", choices.clone(), Duration::from_secs(60), now).is_some());
+        // Only one poll at a time
+        assert!(polls.start("again?", choices, Duration::from_secs(60), now).is_none());
+
+        polls.vote("a", 1);
+        polls.vote("b", 2);
+        assert!(polls.vote("c", 3).is_none());
+        // Changed their mind
+        let poll = polls.vote("b", 1).unwrap();
+        assert_eq!(poll.choices[0].votes, 2);
+        assert_eq!(poll.percent(&poll.choices[0]), 100);
+
+        assert!(polls.expire(now + Duration::from_secs(59)).is_none());
+        let poll = polls.expire(now + Duration::from_secs(60)).unwrap();
+        assert!(poll.finished);
+        assert_eq!(poll.winner(), Some(0));
+        assert!(polls.end().is_none());
+    }
+
+    #[test]
+    fn prediction_winner() {
+        let prediction: Prediction = serde_json::from_str(
+            r#"{"title": "Will it build?", "status": "resolved", "winning_outcome_id": "b", "outcomes": [
+                {"id": "a", "title": "Yes", "channel_points": 500},
+                {"id": "b", "title": "No", "channel_points": 100}
+            ]}"#,
+        )
+        .unwrap();
+        let poll = from_prediction(prediction);
+        assert!(poll.finished);
+        assert_eq!(poll.winner(), Some(1));
+        assert_eq!(poll.percent(&poll.choices[0]), 83);
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::config::ShoutoutConfig;
+use crate::display::models::{ChatEvent, DisplayMessage};
+
+/// Shared between `!so` and the chat pipeline
+pub type SharedShoutouts = Arc<Mutex<Shoutouts>>;
+
+// -----------------------------------------------------------------------------
+//     - Shoutouts -
+//     Remembers, for this session, who chatted, raided, followed
+//     and already got a shoutout. Names are kept lowercase.
+// -----------------------------------------------------------------------------
+pub struct Shoutouts {
+    message: String,
+    streamers: HashSet<String>,
+    seen: HashSet<String>,
+    shouted: HashSet<String>,
+    raiders: HashSet<String>,
+    followers: HashSet<String>,
+}
+
+impl Shoutouts {
+    pub fn new(config: &ShoutoutConfig) -> Self {
+        Self {
+            message: config.message.clone(),
+            streamers: config.streamers.iter().map(|s| s.to_lowercase()).collect(),
+            seen: HashSet::new(),
+            shouted: HashSet::new(),
+            raiders: HashSet::new(),
+            followers: HashSet::new(),
+        }
+    }
+
+    pub fn shared(config: &ShoutoutConfig) -> SharedShoutouts {
+        Arc::new(Mutex::new(Self::new(config)))
+    }
+
+    /// Prompt for a shoutout the first time a known streamer chats
+    pub fn chat(&mut self, nick: &str) -> Option<DisplayMessage> {
+        let name = nick.to_lowercase();
+        let first = self.seen.insert(name.clone());
+        if !first || !self.streamers.contains(&name) || self.shouted.contains(&name) {
+            return None;
+        }
+        Some(prompt(&format!("{} is here", nick), nick))
+    }
+
+    pub fn raid(&mut self, name: &str) -> DisplayMessage {
+        self.raiders.insert(name.to_lowercase());
+        prompt(&format!("{} raided", name), name)
+    }
+
+    pub fn follow(&mut self, name: &str) {
+        self.followers.insert(name.to_lowercase());
+    }
+
+    /// The shoutout for `user`, with a thank you if they raided or followed
+    pub fn shoutout(&mut self, user: &str) -> String {
+        let name = user.to_lowercase();
+        self.shouted.insert(name.clone());
+
+        let message = self.message.replace("{user}", user).replace("{login}", &name);
+        if self.raiders.contains(&name) {
+            format!("{} Thank you for the raid!", message)
+        } else if self.followers.contains(&name) {
+            format!("{} Thank you for the follow!", message)
+        } else {
+            message
+        }
+    }
+}
+
+fn prompt(reason: &str, name: &str) -> DisplayMessage {
+    DisplayMessage::ChatEvent(ChatEvent(format!("{}, shout out? !so {}", reason, name)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn shoutouts() -> Shoutouts {
+        let config = ShoutoutConfig { streamers: vec!["SomeStreamer".to_string()], message: "Check out {user}".into() };
+        Shoutouts::new(&config)
+    }
+
+    #[test]
+    fn prompt_once_for_streamers() {
+        let mut shoutouts = shoutouts();
+        assert!(shoutouts.chat("viewer").is_none());
+        assert!(shoutouts.chat("somestreamer").is_some());
+        assert!(shoutouts.chat("SomeStreamer").is_none());
+    }
+
+    #[test]
+    fn no_prompt_after_shoutout() {
+        let mut shoutouts = shoutouts();
+        assert_eq!(shoutouts.shoutout("SomeStreamer"), "Check out SomeStreamer");
+        assert!(shoutouts.chat("somestreamer").is_none());
+    }
+
+    #[test]
+    fn remember_raiders() {
+        let mut shoutouts = shoutouts();
+        shoutouts.raid("Raider");
+        shoutouts.follow("Follower");
+        assert_eq!(shoutouts.shoutout("raider"), "Check out raider Thank you for the raid!");
+        assert_eq!(shoutouts.shoutout("Follower"), "Check out Follower Thank you for the follow!");
+    }
+}
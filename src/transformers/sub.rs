@@ -2,22 +2,91 @@ use std::time::{Instant, Duration};
 
 use neotwitch::SubscribeEvent;
 
-use crate::display::models::{Subscription, SubType, Tier};
+use crate::display::models::{DisplayMessage, GiftBomb, Subscription, SubType, Tier};
+use crate::twitch::CommunityGift;
 
 const SUB_DRAIN_LIFE_SECS: u64 = 2;
+// Give up on recipients that never show up
+const GIFT_BOMB_LIFE_SECS: u64 = 60;
+const ANON_GIFTER: &str = "An Anonymous Gifter";
+
+struct PendingBomb {
+    started: Instant,
+    count: usize,
+    sub: Subscription,
+}
 
 pub struct SubTransformer {
-    subs: Vec<(Instant, Subscription)>
+    subs: Vec<(Instant, Subscription)>,
+    bombs: Vec<PendingBomb>,
+}
+
+impl PendingBomb {
+    // Anonymous community gifts have no name, but the gift subs
+    // that follow are from "An Anonymous Gifter"
+    fn is_from(&self, sub: &SubscribeEvent) -> bool {
+        match &self.sub.display_name {
+            Some(name) => sub.display_name.as_ref() == Some(name),
+            None => sub.context.starts_with("anon") || sub.display_name.as_deref() == Some(ANON_GIFTER),
+        }
+    }
 }
 
 impl SubTransformer {
     pub fn new() -> Self {
         Self {
             subs: Vec::new(),
+            bombs: Vec::new(),
         }
     }
 
-    pub fn transform(&mut self, sub: SubscribeEvent) {
+    /// Someone is gifting a bunch of subs at once.
+    /// The recipients arrive as separate gift subs afterwards.
+    pub fn community_gift(&mut self, gift: CommunityGift, sound_path: String) -> DisplayMessage {
+        let tier = Tier::from(gift.sub_plan);
+        let sub = Subscription {
+            gift: true,
+            display_name: gift.display_name.clone(),
+            recipients: Vec::new(),
+            tier,
+            message: String::new(),
+            sub_type: match gift.display_name {
+                Some(_) => SubType::Gift,
+                None => SubType::AnonGift,
+            },
+            cumulative_months: None,
+            streak: None,
+            multi_month: None,
+        };
+        self.bombs.push(PendingBomb { started: Instant::now(), count: gift.mass_gift_count, sub });
+
+        let bomb = GiftBomb {
+            gifter: gift.display_name.unwrap_or_else(|| "[Anonymous]".to_string()),
+            count: gift.mass_gift_count,
+            tier,
+            lifetime_total: gift.sender_count,
+            recipients: Vec::new(),
+        };
+        DisplayMessage::GiftBomb(bomb, sound_path)
+    }
+
+    pub fn transform(&mut self, sub: SubscribeEvent) -> Option<DisplayMessage> {
+        // Recipients of a community gift go to the gift bomb
+        if sub.is_gift {
+            let bomb = self
+                .bombs
+                .iter_mut()
+                .find(|b| b.is_from(&sub) && b.sub.recipients.len() < b.count);
+
+            if let (Some(bomb), Some(recipient)) = (bomb, sub.recipient_display_name.as_ref()) {
+                bomb.sub.recipients.push(recipient.clone());
+                return Some(DisplayMessage::GiftBombRecipient {
+                    gifter: bomb.sub.display_name.clone().unwrap_or_else(|| "[Anonymous]".to_string()),
+                    recipient: recipient.clone(),
+                });
+            }
+        }
+
         let updated_existing = self.subs
             .iter_mut()
             .filter(|(_, s)| s.display_name == sub.display_name && s.gift)
@@ -33,8 +102,8 @@ impl SubTransformer {
             let sub_type = match sub.context.as_ref() {
                 "sub" => SubType::NewSub,
                 "resub" => SubType::Resub,
-                "subgift" | "anonsubgift" => SubType::Gift,
-                "resubgift" | "anonresubgift" => SubType::Resub,
+                "subgift" | "resubgift" => SubType::Gift,
+                "anonsubgift" | "anonresubgift" => SubType::AnonGift,
                 _ => SubType::Unknown
             };
 
@@ -46,10 +115,31 @@ impl SubTransformer {
                 message: sub.sub_message.message,
                 cumulative_months: sub.cumulative_months,
                 streak: sub.streak_months,
+                multi_month: sub.multi_month_duration,
                 sub_type,
             };
             self.subs.push((Instant::now(), sub));
         }
+
+        None
+    }
+
+    /// Gift bombs that have all their recipients, or have given up waiting.
+    /// These have already been shown, so they're only needed for the stats.
+    pub fn finished_bombs(&mut self) -> Vec<Subscription> {
+        let mut finished = vec![];
+        let mut i = 0;
+        while i < self.bombs.len() {
+            let bomb = &self.bombs[i];
+            let complete = bomb.sub.recipients.len() >= bomb.count;
+            if complete || bomb.started.elapsed() > Duration::from_secs(GIFT_BOMB_LIFE_SECS) {
+                finished.push(self.bombs.remove(i).sub);
+            } else {
+                i += 1;
+            }
+        }
+
+        finished
     }
 
     pub fn outstanding(&mut self) -> Vec<Subscription> {
@@ -105,6 +195,27 @@ mod test {
         assert_eq!(transformer.subs.len(), 1);
     }
 
+    #[test]
+    fn gift_bomb_takes_recipients() {
+        let mut transformer = SubTransformer::new();
+        let gift = CommunityGift {
+            display_name: Some("user".to_string()),
+            mass_gift_count: 5,
+            sender_count: Some(50),
+            sub_plan: "1000".into(),
+        };
+        transformer.community_gift(gift, String::new());
+
+        for s in gift_five_subs() {
+            assert!(matches!(transformer.transform(s), Some(DisplayMessage::GiftBombRecipient { .. })));
+        }
+        assert!(transformer.subs.is_empty());
+
+        let finished = transformer.finished_bombs();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].recipients.len(), 5);
+    }
+
     fn make_new_sub() -> SubscribeEvent {
         SubscribeEvent {
             display_name: Some("user".to_string()),
@@ -0,0 +1,116 @@
+use crate::config::TtsConfig;
+use crate::display::models::{DisplayMessage, Speech};
+
+// Runs of the same character longer than this are cut down
+const MAX_REPEAT: usize = 3;
+
+/// Decides what gets read out loud, and in which voice.
+pub struct TtsTransformer {
+    config: TtsConfig,
+}
+
+impl TtsTransformer {
+    pub fn new(config: TtsConfig) -> Self {
+        Self { config }
+    }
+
+    /// Read the user input of a redemption, if the reward has a voice
+    pub fn redemption(&self, reward: &str, user_input: Option<&str>) -> Option<DisplayMessage> {
+        let voice = self.config.rewards.get(reward)?;
+        self.speech(user_input?, voice)
+    }
+
+    /// Read the message that came with a big enough cheer
+    pub fn bits(&self, name: &str, amount: usize, message: &str) -> Option<DisplayMessage> {
+        if amount < self.config.bits_min {
+            return None;
+        }
+        let message = strip_cheermotes(message);
+        let text = match message.trim().is_empty() {
+            true => format!("{} cheered {} bits", name, amount),
+            false => format!("{} says: {}", name, message),
+        };
+        self.speech(&text, &self.config.default_voice)
+    }
+
+    fn speech(&self, text: &str, voice: &str) -> Option<DisplayMessage> {
+        if !self.config.enabled {
+            return None;
+        }
+        let text = clean(text, self.config.max_len, &self.config.blocked_words)?;
+        Some(DisplayMessage::Speak(Speech { text, voice: voice.to_string() }))
+    }
+}
+
+// Cheermotes are a word followed by the amount, e.g. "Cheer100"
+fn strip_cheermotes(message: &str) -> String {
+    let is_cheermote = |word: &str| {
+        let digits = word.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        digits.len() < word.len() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+    };
+    message.split_whitespace().filter(|word| !is_cheermote(word)).collect::<Vec<_>>().join(" ")
+}
+
+// Make text fit to be read out: no links, no keyboard mashing
+// and not too long. None if there is nothing left or it's blocked.
+fn clean(text: &str, max_len: usize, blocked_words: &[String]) -> Option<String> {
+    // Whole words only, so blocking "ass" doesn't block "class"
+    let lower = text.to_lowercase();
+    let mut text_words = lower.split_whitespace().map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()));
+    if text_words.any(|w| blocked_words.iter().any(|word| word.to_lowercase() == w)) {
+        log::info!("Not reading out \"{}\"", text);
+        return None;
+    }
+
+    let words = text.split_whitespace().filter(|word| !word.contains("://") && !word.starts_with("www."));
+
+    let mut cleaned = String::new();
+    for word in words {
+        let mut last = None;
+        let mut repeat = 0;
+        let word = word
+            .chars()
+            .filter(|c| {
+                repeat = if last == Some(*c) { repeat + 1 } else { 1 };
+                last = Some(*c);
+                repeat <= MAX_REPEAT
+            })
+            .collect::<String>();
+
+        let space = !cleaned.is_empty() as usize;
+        if cleaned.chars().count() + space + word.chars().count() > max_len {
+            break;
+        }
+        if !cleaned.is_empty() {
+            cleaned.push(' ');
+        }
+        cleaned.push_str(&word);
+    }
+
+    match cleaned.is_empty() {
+        true => None,
+        false => Some(cleaned),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clean_text() {
+        let blocked = vec!["badword".to_string()];
+        assert_eq!(clean("hiiiiiii there https://example.com", 200, &blocked).unwrap(), "hiii there");
+        assert_eq!(clean("one two three", 8, &blocked).unwrap(), "one two");
+        assert!(clean("a BadWord here", 200, &blocked).is_none());
+        assert!(clean("a badword!", 200, &blocked).is_none());
+        assert_eq!(clean("notbadwords", 200, &blocked).unwrap(), "notbadwords");
+        assert_eq!(clean("eight ch", 5, &blocked).unwrap(), "eight");
+        assert!(clean("www.example.com", 200, &blocked).is_none());
+    }
+
+    #[test]
+    fn cheermotes() {
+        assert_eq!(strip_cheermotes("Cheer100 hello uni244 2022 world"), "hello 2022 world");
+    }
+}
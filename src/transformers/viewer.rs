@@ -0,0 +1,79 @@
+use chrono::{Local, TimeZone, Utc};
+
+use crate::config::ViewerConfig;
+use crate::display::models::{Highlight, Subscription};
+use crate::viewers::{Arrival, Viewers};
+
+/// Keeps the viewer database up to date.
+/// Without a database everything is a no-op.
+pub struct ViewerTransformer {
+    viewers: Option<Viewers>,
+    welcome_back: Option<String>,
+}
+
+impl ViewerTransformer {
+    pub fn new(config: &ViewerConfig) -> Self {
+        let viewers = match Viewers::open(&config.path, config.away_hours * 60 * 60) {
+            Ok(viewers) => Some(viewers),
+            Err(e) => {
+                log::error!("No viewer database at {}: {}", config.path.display(), e);
+                None
+            }
+        };
+        Self { viewers, welcome_back: config.welcome_back.clone() }
+    }
+
+    /// Count a chat message.
+    /// Gives back how to highlight the message, and a greeting for returning viewers.
+    pub fn chat(&mut self, nick: &str) -> (Option<Highlight>, Option<String>) {
+        let viewers = match self.viewers.as_mut() {
+            Some(viewers) => viewers,
+            None => return (None, None),
+        };
+
+        match viewers.chat(nick, Utc::now().timestamp()) {
+            Ok(Arrival::First) => (Some(Highlight::FirstTime), None),
+            Ok(Arrival::Returning(last_seen)) => {
+                let last_seen = match Local.timestamp_opt(last_seen, 0).single() {
+                    Some(time) => time.format("%Y-%m-%d").to_string(),
+                    None => "a while ago".to_string(),
+                };
+                let greeting = self
+                    .welcome_back
+                    .as_ref()
+                    .map(|text| text.replace("{nick}", nick).replace("{last_seen}", &last_seen));
+                (Some(Highlight::Returning), greeting)
+            }
+            Ok(Arrival::Seen) => (None, None),
+            Err(e) => {
+                log::error!("Failed to update viewer {}: {}", nick, e);
+                (None, None)
+            }
+        }
+    }
+
+    pub fn sub(&mut self, sub: &Subscription) {
+        let viewers = match self.viewers.as_mut() {
+            Some(viewers) => viewers,
+            None => return,
+        };
+
+        let now = Utc::now().timestamp();
+        let result = match (sub.gift, sub.display_name.as_deref()) {
+            (true, _) => sub.recipients.iter().try_for_each(|name| viewers.sub(name, None, now)),
+            (false, Some(name)) => viewers.sub(name, sub.cumulative_months, now),
+            (false, None) => Ok(()),
+        };
+        if let Err(e) = result {
+            log::error!("Failed to update subscriber: {}", e);
+        }
+    }
+
+    pub fn follow(&mut self, name: &str) {
+        if let Some(viewers) = self.viewers.as_mut() {
+            if let Err(e) = viewers.follow(name, Utc::now().timestamp()) {
+                log::error!("Failed to update follower {}: {}", name, e);
+            }
+        }
+    }
+}
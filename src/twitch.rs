@@ -2,11 +2,14 @@ use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use neotwitch::{BitsEvent, ChannelPoints, ChannelPointsEvent, FollowEvent, Irc, SubscribeEvent, TwitchMessage};
+use serde::Deserialize;
 use tinyroute::client::{connect, ClientMessage, TcpClient};
 use tinyroute::frame::Frame;
 use tokio::time;
 use log::error;
 
+use crate::control::{self, Control};
+
 const MAX_RETRIES: usize = 500;
 
 #[derive(Debug)]
@@ -15,6 +18,62 @@ pub enum Twitch {
     ChannelEvent(ChannelPoints),
     Follow(FollowEvent),
     Sub(SubscribeEvent),
+    Raid(Raid),
+    CommunityGift(CommunityGift),
+    Poll(TwitchPoll),
+    Prediction(Prediction),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Raid {
+    pub display_name: String,
+    pub viewer_count: usize,
+}
+
+/// "X is gifting N subs to the community".
+/// The individual gift subs follow as regular sub events.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommunityGift {
+    // None when the gifter is anonymous
+    pub display_name: Option<String>,
+    pub mass_gift_count: usize,
+    // Total number of gifts from this gifter in the channel, if shared
+    pub sender_count: Option<usize>,
+    pub sub_plan: String,
+}
+
+/// Poll progress, sent for every update and once more when it ends
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwitchPoll {
+    pub title: String,
+    pub choices: Vec<TwitchPollChoice>,
+    // "active", "completed", "terminated" or "archived"
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwitchPollChoice {
+    pub title: String,
+    #[serde(default)]
+    pub votes: usize,
+}
+
+/// Prediction progress, shown as a poll of channel points
+#[derive(Debug, Clone, Deserialize)]
+pub struct Prediction {
+    pub title: String,
+    pub outcomes: Vec<PredictionOutcome>,
+    // "active", "locked", "resolved" or "canceled"
+    pub status: String,
+    pub winning_outcome_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PredictionOutcome {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub channel_points: usize,
 }
 
 pub async fn start(tx: crate::EventSender) {
@@ -55,7 +114,17 @@ async fn run(tx: crate::EventSender, client: TcpClient) -> Result<()> {
     let framed_message = Frame::frame_message(msg);
     client_tx.send(ClientMessage::Payload(framed_message))?;
 
+    let mut msg = control::CHANNEL.to_vec();
+    msg.extend_from_slice(b"|sub");
+    let framed_message = Frame::frame_message(&msg);
+    client_tx.send(ClientMessage::Payload(framed_message))?;
+
     while let Ok(bytes) = client_rx.recv_async().await {
+        if let Some(control) = Control::parse(&bytes) {
+            let _ = tx.send(crate::Event::Control(control)).await;
+            continue;
+        }
+
         match serde_json::from_slice::<Irc>(&bytes) {
             Ok(irc_msg) => {
                 drop(tx.send(crate::Event::from_irc(irc_msg).into()).await);
@@ -85,6 +154,22 @@ async fn run(tx: crate::EventSender, client: TcpClient) -> Result<()> {
                                 serde_json::from_str(&twitch_msg.message).expect("it's that good ole json");
                             let _ = tx.send(crate::Event::from_follow(data).into()).await;
                         }
+                        "raid" => match serde_json::from_str::<Raid>(&twitch_msg.message) {
+                            Ok(raid) => drop(tx.send(crate::Event::Twitch(Twitch::Raid(raid))).await),
+                            Err(e) => error!("Invalid raid: {}", e),
+                        },
+                        "community-gift" => match serde_json::from_str::<CommunityGift>(&twitch_msg.message) {
+                            Ok(gift) => drop(tx.send(crate::Event::Twitch(Twitch::CommunityGift(gift))).await),
+                            Err(e) => error!("Invalid community gift: {}", e),
+                        },
+                        "poll" => match serde_json::from_str::<TwitchPoll>(&twitch_msg.message) {
+                            Ok(poll) => drop(tx.send(crate::Event::Twitch(Twitch::Poll(poll))).await),
+                            Err(e) => error!("Invalid poll: {}", e),
+                        },
+                        "prediction" => match serde_json::from_str::<Prediction>(&twitch_msg.message) {
+                            Ok(prediction) => drop(tx.send(crate::Event::Twitch(Twitch::Prediction(prediction))).await),
+                            Err(e) => error!("Invalid prediction: {}", e),
+                        },
                         "channel-subscribe-events-v1" => {
                             let sub = serde_json::from_str::<SubscribeEvent>(&twitch_msg.message).expect("yay");
                             let _ = tx.send(crate::Event::from_sub(sub).into()).await;
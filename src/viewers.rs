@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Everything remembered about a viewer, across sessions.
+/// Times are unix timestamps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Viewer {
+    pub name: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub messages: usize,
+    pub sub_months: usize,
+    pub followed: Option<i64>,
+}
+
+impl Viewer {
+    fn new(name: &str, now: i64) -> Self {
+        Self { name: name.to_string(), first_seen: now, last_seen: now, messages: 0, sub_months: 0, followed: None }
+    }
+}
+
+/// How a viewer's first message of the session should be treated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrival {
+    /// Never chatted before
+    First,
+    /// Back after being away, with when they were last seen
+    Returning(i64),
+    /// Already chatted this session, or was away too short to count
+    Seen,
+}
+
+// -----------------------------------------------------------------------------
+//     - Viewers -
+//     Viewers by lowercase name in a sled database.
+// -----------------------------------------------------------------------------
+pub struct Viewers {
+    db: sled::Db,
+    // Seconds away before a viewer counts as returning
+    away_secs: i64,
+    session: HashSet<String>,
+}
+
+impl Viewers {
+    pub fn open(path: impl AsRef<Path>, away_secs: i64) -> Result<Self> {
+        Ok(Self::with_db(sled::open(path)?, away_secs))
+    }
+
+    fn with_db(db: sled::Db, away_secs: i64) -> Self {
+        Self { db, away_secs, session: HashSet::new() }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<Viewer>> {
+        match self.db.get(name.to_lowercase())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    // `previous` is the viewer as it was read from the database
+    fn update(&self, name: &str, previous: Option<Viewer>, now: i64, f: impl FnOnce(&mut Viewer)) -> Result<Viewer> {
+        let mut viewer = previous.unwrap_or_else(|| Viewer::new(name, now));
+        f(&mut viewer);
+        self.db.insert(name.to_lowercase(), serde_json::to_vec(&viewer)?)?;
+        Ok(viewer)
+    }
+
+    /// Count a chat message
+    pub fn chat(&mut self, nick: &str, now: i64) -> Result<Arrival> {
+        let previous = self.get(nick)?;
+        let arrival = match &previous {
+            // Followed or subbed, but never chatted
+            Some(viewer) if viewer.messages == 0 => Arrival::First,
+            Some(viewer) if now - viewer.last_seen >= self.away_secs => Arrival::Returning(viewer.last_seen),
+            Some(_) => Arrival::Seen,
+            None => Arrival::First,
+        };
+
+        self.update(nick, previous, now, |viewer| {
+            viewer.messages += 1;
+            viewer.last_seen = now;
+        })?;
+
+        match self.session.insert(nick.to_lowercase()) {
+            true => Ok(arrival),
+            false => Ok(Arrival::Seen),
+        }
+    }
+
+    /// Gift recipients don't know their months, so they count as at least one
+    pub fn sub(&mut self, name: &str, months: Option<usize>, now: i64) -> Result<()> {
+        self.update(name, self.get(name)?, now, |viewer| {
+            viewer.sub_months = viewer.sub_months.max(months.unwrap_or(1));
+        })?;
+        Ok(())
+    }
+
+    pub fn follow(&mut self, name: &str, now: i64) -> Result<()> {
+        self.update(name, self.get(name)?, now, |viewer| {
+            viewer.followed.get_or_insert(now);
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn viewers() -> Viewers {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        Viewers::with_db(db, 100)
+    }
+
+    #[test]
+    fn arrivals() {
+        let mut viewers = viewers();
+        assert_eq!(viewers.chat("Someone", 0).unwrap(), Arrival::First);
+        assert_eq!(viewers.chat("someone", 10).unwrap(), Arrival::Seen);
+
+        // A new session
+        viewers.session.clear();
+        assert_eq!(viewers.chat("someone", 50).unwrap(), Arrival::Seen);
+        viewers.session.clear();
+        assert_eq!(viewers.chat("someone", 500).unwrap(), Arrival::Returning(50));
+
+        let viewer = viewers.get("SOMEONE").unwrap().unwrap();
+        assert_eq!(viewer.messages, 4);
+        assert_eq!(viewer.first_seen, 0);
+    }
+
+    #[test]
+    fn subs_and_follows() {
+        let mut viewers = viewers();
+        viewers.follow("a", 5).unwrap();
+        viewers.sub("a", Some(12), 6).unwrap();
+        viewers.sub("a", None, 7).unwrap();
+        viewers.follow("a", 8).unwrap();
+
+        let viewer = viewers.get("a").unwrap().unwrap();
+        assert_eq!(viewer.sub_months, 12);
+        assert_eq!(viewer.followed, Some(5));
+        assert_eq!(viewers.chat("a", 9).unwrap(), Arrival::First);
+    }
+}